@@ -0,0 +1,514 @@
+//ViewFS挂载表
+pub mod viewfs;
+//WebHDFS/HttpFS REST客户端
+pub mod webhdfs;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bytes::Bytes;
+use hdfs_native::WriteOptions;
+use hdfs_native::acl::AclEntry;
+
+use viewfs::{ViewFsClient, ViewFsListIterator};
+use webhdfs::{WebHdfsClient, WebHdfsListIterator, WebHdfsReader, WebHdfsWriter};
+
+//文件不存在的错误前缀,与namenode返回的异常名一致
+pub const FILE_NOT_FOUND: &str = "FileNotFoundException";
+
+pub fn is_file_not_found(error: &str) -> bool {
+    error.starts_with(FILE_NOT_FOUND)
+}
+
+//认证失败的错误,如票据过期、SASL握手失败或令牌失效
+const AUTH_ERRORS: [&str; 6] = [
+    "gssapi",
+    "sasl",
+    "invalidtoken",
+    "authenticationexception",
+    "cannot authenticate",
+    "kerberos",
+];
+
+pub fn is_auth_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    AUTH_ERRORS.iter().any(|e| error.contains(e))
+}
+
+//文件状态,两种后端返回相同的结构
+#[derive(Debug, Clone)]
+pub struct FileStatus {
+    pub path: String,
+    pub length: usize,
+    pub isdir: bool,
+    pub permission: u16,
+    pub owner: String,
+    pub group: String,
+    pub modification_time: u64,
+    pub access_time: u64,
+}
+
+impl From<hdfs_native::client::FileStatus> for FileStatus {
+    fn from(status: hdfs_native::client::FileStatus) -> Self {
+        FileStatus {
+            path: status.path,
+            length: status.length,
+            isdir: status.isdir,
+            permission: status.permission,
+            owner: status.owner,
+            group: status.group,
+            modification_time: status.modification_time,
+            access_time: status.access_time,
+        }
+    }
+}
+
+//目录内容摘要
+#[derive(Debug, Clone)]
+pub struct ContentSummary {
+    pub length: u64,
+    pub file_count: u64,
+    pub directory_count: u64,
+    pub quota: u64,
+    pub space_consumed: u64,
+    pub space_quota: u64,
+}
+
+impl From<hdfs_native::client::ContentSummary> for ContentSummary {
+    fn from(summary: hdfs_native::client::ContentSummary) -> Self {
+        ContentSummary {
+            length: summary.length,
+            file_count: summary.file_count,
+            directory_count: summary.directory_count,
+            quota: summary.quota,
+            space_consumed: summary.space_consumed,
+            space_quota: summary.space_quota,
+        }
+    }
+}
+
+//acl状态
+#[derive(Debug, Clone)]
+pub struct AclStatus {
+    pub owner: String,
+    pub group: String,
+    pub sticky: bool,
+    pub permission: u16,
+    pub entries: Vec<AclEntry>,
+}
+
+impl From<hdfs_native::acl::AclStatus> for AclStatus {
+    fn from(status: hdfs_native::acl::AclStatus) -> Self {
+        AclStatus {
+            owner: status.owner,
+            group: status.group,
+            sticky: status.sticky,
+            permission: status.permission,
+            entries: status.entries,
+        }
+    }
+}
+
+//rpc客户端,认证失败后标记为需要重建,重建时重新读取票据缓存
+pub struct RpcClient {
+    client: Arc<hdfs_native::Client>,
+    auth_failed: AtomicBool,
+}
+
+impl RpcClient {
+    pub fn new(client: Arc<hdfs_native::Client>) -> Self {
+        RpcClient {
+            client,
+            auth_failed: AtomicBool::new(false),
+        }
+    }
+
+    fn error(&self, e: hdfs_native::HdfsError) -> String {
+        let error = e.to_string();
+        if is_auth_error(&error) {
+            self.auth_failed.store(true, Ordering::Relaxed);
+        }
+        error
+    }
+}
+
+impl std::ops::Deref for RpcClient {
+    type Target = hdfs_native::Client;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+//hdfs客户端,hdfs://使用rpc,webhdfs://和swebhdfs://使用REST接口,
+//viewfs://按挂载表使用各挂载点的客户端
+pub enum HdfsClient {
+    Rpc(RpcClient),
+    WebHdfs(WebHdfsClient),
+    ViewFs(ViewFsClient),
+}
+
+impl HdfsClient {
+    //rpc请求是否认证失败过。webhdfs每次请求重新生成SPNEGO令牌,不需要重建
+    pub fn auth_failed(&self) -> bool {
+        match self {
+            HdfsClient::Rpc(client) => client.auth_failed.load(Ordering::Relaxed),
+            HdfsClient::WebHdfs(_) => false,
+            HdfsClient::ViewFs(client) => client.auth_failed(),
+        }
+    }
+
+    pub async fn get_file_info(&self, path: &str) -> Result<FileStatus, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .get_file_info(path)
+                .await
+                .map(FileStatus::from)
+                .map_err(|e| match e {
+                    hdfs_native::HdfsError::FileNotFound(path) => {
+                        format!("{}: File does not exist: {}", FILE_NOT_FOUND, path)
+                    }
+                    e => client.error(e),
+                }),
+            HdfsClient::WebHdfs(client) => client.get_file_info(path).await,
+            HdfsClient::ViewFs(client) => client.get_file_info(path).await,
+        }
+    }
+
+    pub async fn list_status(
+        &self,
+        path: &str,
+        recursive: bool,
+    ) -> Result<Vec<FileStatus>, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .list_status(path, recursive)
+                .await
+                .map(|statuses| statuses.into_iter().map(FileStatus::from).collect())
+                .map_err(|e| client.error(e)),
+            HdfsClient::ViewFs(client) => client.list_status(path, recursive).await,
+            HdfsClient::WebHdfs(client) if !recursive => client.list_status(path).await,
+            HdfsClient::WebHdfs(client) => {
+                let mut statuses = vec![];
+                let mut iter = client.list_status_iter(path, true);
+                while let Some(status) = iter.next().await {
+                    statuses.push(status?);
+                }
+                Ok(statuses)
+            }
+        }
+    }
+
+    pub fn list_status_iter(&self, path: &str, recursive: bool) -> ListStatusIterator {
+        match self {
+            HdfsClient::Rpc(client) => {
+                ListStatusIterator::Rpc(client.list_status_iter(path, recursive))
+            }
+            HdfsClient::WebHdfs(client) => {
+                ListStatusIterator::WebHdfs(client.list_status_iter(path, recursive))
+            }
+            HdfsClient::ViewFs(client) => {
+                ListStatusIterator::ViewFs(client.list_status_iter(path, recursive))
+            }
+        }
+    }
+
+    pub async fn get_content_summary(&self, path: &str) -> Result<ContentSummary, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .get_content_summary(path)
+                .await
+                .map(ContentSummary::from)
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.get_content_summary(path).await,
+            HdfsClient::ViewFs(client) => client.get_content_summary(path).await,
+        }
+    }
+
+    pub async fn read(&self, path: &str) -> Result<HdfsFileReader, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .read(path)
+                .await
+                .map(HdfsFileReader::Rpc)
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.read(path).await.map(HdfsFileReader::WebHdfs),
+            HdfsClient::ViewFs(client) => client.read(path).await,
+        }
+    }
+
+    pub async fn create(
+        &self,
+        path: &str,
+        options: WriteOptions,
+    ) -> Result<HdfsFileWriter, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .create(path, options)
+                .await
+                .map(HdfsFileWriter::Rpc)
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client
+                .create(path, &options)
+                .await
+                .map(HdfsFileWriter::WebHdfs),
+            HdfsClient::ViewFs(client) => client.create(path, options).await,
+        }
+    }
+
+    pub async fn append(&self, path: &str) -> Result<HdfsFileWriter, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .append(path)
+                .await
+                .map(HdfsFileWriter::Rpc)
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.append(path).await.map(HdfsFileWriter::WebHdfs),
+            HdfsClient::ViewFs(client) => client.append(path).await,
+        }
+    }
+
+    pub async fn mkdirs(
+        &self,
+        path: &str,
+        permission: u32,
+        create_parent: bool,
+    ) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .mkdirs(path, permission, create_parent)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.mkdirs(path, permission, create_parent).await,
+            HdfsClient::ViewFs(client) => client.mkdirs(path, permission, create_parent).await,
+        }
+    }
+
+    pub async fn rename(&self, src: &str, dst: &str, overwrite: bool) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .rename(src, dst, overwrite)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.rename(src, dst, overwrite).await,
+            HdfsClient::ViewFs(client) => client.rename(src, dst, overwrite).await,
+        }
+    }
+
+    pub async fn delete(&self, path: &str, recursive: bool) -> Result<bool, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .delete(path, recursive)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.delete(path, recursive).await,
+            HdfsClient::ViewFs(client) => client.delete(path, recursive).await,
+        }
+    }
+
+    pub async fn set_permission(&self, path: &str, permission: u32) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .set_permission(path, permission)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.set_permission(path, permission).await,
+            HdfsClient::ViewFs(client) => client.set_permission(path, permission).await,
+        }
+    }
+
+    //修改所有者和组,None时不修改
+    pub async fn set_owner(
+        &self,
+        path: &str,
+        owner: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .set_owner(path, owner, group)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.set_owner(path, owner, group).await,
+            HdfsClient::ViewFs(client) => client.set_owner(path, owner, group).await,
+        }
+    }
+
+    //修改时间和访问时间,单位为毫秒,None时不修改
+    pub async fn set_times(
+        &self,
+        path: &str,
+        mtime: Option<u64>,
+        atime: Option<u64>,
+    ) -> Result<(), String> {
+        match self {
+            //namenode把-1当作不修改
+            HdfsClient::Rpc(client) => client
+                .set_times(path, mtime.unwrap_or(u64::MAX), atime.unwrap_or(u64::MAX))
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.set_times(path, mtime, atime).await,
+            HdfsClient::ViewFs(client) => client.set_times(path, mtime, atime).await,
+        }
+    }
+
+    pub async fn get_acl_status(&self, path: &str) -> Result<AclStatus, String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .get_acl_status(path)
+                .await
+                .map(AclStatus::from)
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.get_acl_status(path).await,
+            HdfsClient::ViewFs(client) => client.get_acl_status(path).await,
+        }
+    }
+
+    pub async fn modify_acl_entries(
+        &self,
+        path: &str,
+        entries: Vec<AclEntry>,
+    ) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .modify_acl_entries(path, entries)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.modify_acl_entries(path, &entries).await,
+            HdfsClient::ViewFs(client) => client.modify_acl_entries(path, entries).await,
+        }
+    }
+
+    pub async fn remove_acl_entries(
+        &self,
+        path: &str,
+        entries: Vec<AclEntry>,
+    ) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .remove_acl_entries(path, entries)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.remove_acl_entries(path, &entries).await,
+            HdfsClient::ViewFs(client) => client.remove_acl_entries(path, entries).await,
+        }
+    }
+
+    pub async fn remove_default_acl(&self, path: &str) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client
+                .remove_default_acl(path)
+                .await
+                .map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.remove_default_acl(path).await,
+            HdfsClient::ViewFs(client) => client.remove_default_acl(path).await,
+        }
+    }
+
+    //移入user的回收站/user/<user>/.Trash/Current,保留原路径。
+    //viewfs时移入文件所在文件系统自己的回收站,由该namenode定期清理
+    pub async fn move_to_trash(&self, path: &str, user: &str) -> Result<(), String> {
+        if let HdfsClient::ViewFs(client) = self {
+            return client.move_to_trash(path, user).await;
+        }
+        let trash_path = format!("/user/{}/.Trash/Current{}", user, path);
+        if let Some(parent) = std::path::Path::new(&trash_path).parent() {
+            self.mkdirs(parent.to_str().unwrap_or_default(), 0o755, true)
+                .await?;
+        }
+        self.rename(path, &trash_path, true).await
+    }
+
+    pub async fn remove_acl(&self, path: &str) -> Result<(), String> {
+        match self {
+            HdfsClient::Rpc(client) => client.remove_acl(path).await.map_err(|e| client.error(e)),
+            HdfsClient::WebHdfs(client) => client.remove_acl(path).await,
+            HdfsClient::ViewFs(client) => client.remove_acl(path).await,
+        }
+    }
+}
+
+//文件读取
+pub enum HdfsFileReader {
+    Rpc(hdfs_native::file::FileReader),
+    WebHdfs(WebHdfsReader),
+}
+
+impl HdfsFileReader {
+    pub fn file_length(&self) -> usize {
+        match self {
+            HdfsFileReader::Rpc(reader) => reader.file_length(),
+            HdfsFileReader::WebHdfs(reader) => reader.file_length(),
+        }
+    }
+
+    //从当前位置读取最多len字节,读到末尾时返回空
+    pub async fn read(&mut self, len: usize) -> Result<Bytes, String> {
+        match self {
+            HdfsFileReader::Rpc(reader) => reader.read(len).await.map_err(|e| e.to_string()),
+            HdfsFileReader::WebHdfs(reader) => reader.read(len).await,
+        }
+    }
+
+    pub async fn read_range(&self, offset: usize, len: usize) -> Result<Bytes, String> {
+        match self {
+            HdfsFileReader::Rpc(reader) => reader
+                .read_range(offset, len)
+                .await
+                .map_err(|e| e.to_string()),
+            HdfsFileReader::WebHdfs(reader) => reader.read_range(offset, len).await,
+        }
+    }
+
+    pub async fn read_range_buf(&self, buf: &mut [u8], offset: usize) -> Result<(), String> {
+        match self {
+            HdfsFileReader::Rpc(reader) => reader
+                .read_range_buf(buf, offset)
+                .await
+                .map_err(|e| e.to_string()),
+            HdfsFileReader::WebHdfs(reader) => reader.read_range_buf(buf, offset).await,
+        }
+    }
+}
+
+//文件写入,写完后必须close
+pub enum HdfsFileWriter {
+    Rpc(hdfs_native::file::FileWriter),
+    WebHdfs(WebHdfsWriter),
+}
+
+impl HdfsFileWriter {
+    pub async fn write(&mut self, buf: Bytes) -> Result<usize, String> {
+        match self {
+            HdfsFileWriter::Rpc(writer) => writer.write(buf).await.map_err(|e| e.to_string()),
+            HdfsFileWriter::WebHdfs(writer) => writer.write(buf).await,
+        }
+    }
+
+    pub async fn close(&mut self) -> Result<(), String> {
+        match self {
+            HdfsFileWriter::Rpc(writer) => writer.close().await.map_err(|e| e.to_string()),
+            HdfsFileWriter::WebHdfs(writer) => writer.close().await,
+        }
+    }
+}
+
+//逐个返回目录下的文件,recursive时包括子目录
+pub enum ListStatusIterator {
+    Rpc(hdfs_native::client::ListStatusIterator),
+    WebHdfs(WebHdfsListIterator),
+    ViewFs(ViewFsListIterator),
+}
+
+impl ListStatusIterator {
+    pub async fn next(&mut self) -> Option<Result<FileStatus, String>> {
+        match self {
+            ListStatusIterator::Rpc(iter) => iter
+                .next()
+                .await
+                .map(|status| status.map(FileStatus::from).map_err(|e| e.to_string())),
+            ListStatusIterator::WebHdfs(iter) => iter.next().await,
+            ListStatusIterator::ViewFs(iter) => iter.next().await,
+        }
+    }
+}
@@ -0,0 +1,650 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use hdfs_native::WriteOptions;
+use hdfs_native::acl::AclEntry;
+
+use super::{
+    AclStatus, ContentSummary, FILE_NOT_FOUND, FileStatus, HdfsClient, HdfsFileReader,
+    HdfsFileWriter,
+};
+
+//挂载表配置前缀,完整的键为fs.viewfs.mounttable.<cluster>.link.<path>
+pub const MOUNT_TABLE_PREFIX: &str = "fs.viewfs.mounttable.";
+//viewfs:///未写cluster时使用的挂载表
+const DEFAULT_MOUNT_TABLE: &str = "default";
+//挂载点之间的虚拟目录权限,与hadoop的ViewFileSystem一致
+const INTERNAL_DIR_PERMISSION: u16 = 0o555;
+
+//挂载点配置
+#[derive(Debug, Clone)]
+pub struct MountLink {
+    //viewfs中的路径
+    pub path: String,
+    //目标地址,如hdfs://ns1/data
+    pub target: String,
+}
+
+//挂载表
+#[derive(Debug, Clone, Default)]
+pub struct MountTable {
+    //配置键的前缀,fs.viewfs.mounttable.<cluster>.
+    pub prefix: String,
+    pub links: Vec<MountLink>,
+    //不在任何挂载点下的路径交给linkFallback
+    pub fallback: Option<String>,
+    //暂不支持的挂载方式(linkMerge、linkRegex等)的配置键
+    pub unsupported: Vec<String>,
+}
+
+impl MountTable {
+    //全部挂载目标,包括linkFallback
+    pub fn targets(&self) -> Vec<&str> {
+        self.links
+            .iter()
+            .map(|link| link.target.as_str())
+            .chain(self.fallback.as_deref())
+            .collect()
+    }
+}
+
+pub fn is_viewfs_url(hdfs_url: &str) -> bool {
+    hdfs_url.starts_with("viewfs://")
+}
+
+//规范化路径:以/开头,去掉重复和末尾的/
+pub fn normalize_path(path: &str) -> String {
+    let names: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    format!("/{}", names.join("/"))
+}
+
+//读取hdfs_url对应cluster的挂载表
+pub fn mount_table(hdfs_url: &str, config: &HashMap<String, String>) -> MountTable {
+    let cluster = hdfs_url
+        .trim_start_matches("viewfs://")
+        .split('/')
+        .next()
+        .filter(|c| !c.is_empty())
+        .unwrap_or(DEFAULT_MOUNT_TABLE);
+    let prefix = format!("{}{}.", MOUNT_TABLE_PREFIX, cluster);
+    let mut table = MountTable {
+        prefix: prefix.clone(),
+        ..Default::default()
+    };
+    for (key, value) in config.iter() {
+        let Some(rest) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+        if let Some(path) = rest.strip_prefix("link.") {
+            table.links.push(MountLink {
+                path: normalize_path(path),
+                target: value.trim().to_string(),
+            });
+        } else if rest == "linkFallback" {
+            table.fallback = Some(value.trim().to_string());
+        } else if rest.starts_with("link") {
+            table.unsupported.push(key.clone());
+        }
+    }
+    table.links.sort_by(|a, b| a.path.cmp(&b.path));
+    table
+}
+
+//把挂载目标拆成文件系统地址和路径,如hdfs://ns1/data拆成hdfs://ns1和/data
+pub fn split_target(target: &str) -> Result<(String, String), String> {
+    let (scheme, rest) = target
+        .split_once("://")
+        .ok_or_else(|| format!("{} is not a filesystem uri", target))?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if authority.is_empty() {
+        return Err(format!("{} has no nameservice or host", target));
+    }
+    Ok((format!("{}://{}", scheme, authority), normalize_path(path)))
+}
+
+//path在mount下时返回剩余部分(以/开头或为空)
+fn relative<'a>(path: &'a str, mount: &str) -> Option<&'a str> {
+    if mount == "/" {
+        return Some(if path == "/" { "" } else { path });
+    }
+    match path.strip_prefix(mount) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+fn join(base: &str, rest: &str) -> String {
+    if rest.is_empty() {
+        return base.to_string();
+    }
+    format!("{}{}", base.trim_end_matches('/'), rest)
+}
+
+struct Mount {
+    path: String,
+    target: String,
+    client: Arc<HdfsClient>,
+}
+
+impl Mount {
+    //目标文件系统中的路径转回viewfs路径
+    fn view_status(&self, mut status: FileStatus) -> FileStatus {
+        if let Some(rest) = relative(&status.path, &self.target) {
+            status.path = join(&self.path, rest);
+        }
+        status
+    }
+}
+
+enum Resolved<'a> {
+    //挂载点(或linkFallback)下的路径
+    Target(&'a Mount, String),
+    //挂载点之间的虚拟目录
+    Internal(String),
+}
+
+struct ViewFsInner {
+    //按路径从长到短排列,优先匹配最深的挂载点
+    mounts: Vec<Mount>,
+    fallback: Option<Mount>,
+}
+
+//ViewFS客户端,按挂载表把路径交给对应文件系统的客户端
+#[derive(Clone)]
+pub struct ViewFsClient(Arc<ViewFsInner>);
+
+fn internal_dir_status(path: &str) -> FileStatus {
+    FileStatus {
+        path: path.to_string(),
+        length: 0,
+        isdir: true,
+        permission: INTERNAL_DIR_PERMISSION,
+        owner: String::new(),
+        group: String::new(),
+        modification_time: 0,
+        access_time: 0,
+    }
+}
+
+fn read_only(path: &str) -> String {
+    format!(
+        "AccessControlException: {} is a mount table directory and is read-only",
+        path
+    )
+}
+
+impl ViewFsClient {
+    //links为(viewfs路径,目标路径,目标客户端),fallback为(目标路径,目标客户端)
+    pub fn new(
+        links: Vec<(String, String, Arc<HdfsClient>)>,
+        fallback: Option<(String, Arc<HdfsClient>)>,
+    ) -> Self {
+        let mut mounts: Vec<Mount> = links
+            .into_iter()
+            .map(|(path, target, client)| Mount {
+                path,
+                target,
+                client,
+            })
+            .collect();
+        mounts.sort_by_key(|m| std::cmp::Reverse(m.path.len()));
+        let fallback = fallback.map(|(target, client)| Mount {
+            path: "/".to_string(),
+            target,
+            client,
+        });
+        ViewFsClient(Arc::new(ViewFsInner { mounts, fallback }))
+    }
+
+    //任一挂载目标的客户端认证失败过
+    pub fn auth_failed(&self) -> bool {
+        self.0
+            .mounts
+            .iter()
+            .chain(self.0.fallback.iter())
+            .any(|m| m.client.auth_failed())
+    }
+
+    fn is_internal_dir(&self, path: &str) -> bool {
+        path == "/"
+            || self
+                .0
+                .mounts
+                .iter()
+                .any(|m| relative(&m.path, path).is_some_and(|rest| !rest.is_empty()))
+    }
+
+    fn resolve(&self, path: &str) -> Result<Resolved<'_>, String> {
+        let path = normalize_path(path);
+        for mount in self.0.mounts.iter() {
+            if let Some(rest) = relative(&path, &mount.path) {
+                return Ok(Resolved::Target(mount, join(&mount.target, rest)));
+            }
+        }
+        if self.is_internal_dir(&path) {
+            return Ok(Resolved::Internal(path));
+        }
+        match &self.0.fallback {
+            Some(fallback) => Ok(Resolved::Target(fallback, join(&fallback.target, &path))),
+            None => Err(format!(
+                "{}: {} is not under any mount point",
+                FILE_NOT_FOUND, path
+            )),
+        }
+    }
+
+    //虚拟目录下一层的挂载点或虚拟目录
+    fn internal_children(&self, dir: &str) -> Vec<String> {
+        let mut children: Vec<String> = self
+            .0
+            .mounts
+            .iter()
+            .filter_map(|m| relative(&m.path, dir))
+            .filter_map(|rest| rest.split('/').find(|s| !s.is_empty()))
+            .map(|name| join(dir, &format!("/{}", name)))
+            .collect();
+        children.sort();
+        children.dedup();
+        children
+    }
+
+    //挂载点显示为目标目录的状态,目标不可访问时显示为虚拟目录
+    async fn mount_point_status(&self, path: &str) -> FileStatus {
+        let mount = self.0.mounts.iter().find(|m| m.path == path);
+        let Some(mount) = mount else {
+            return internal_dir_status(path);
+        };
+        match mount.client.get_file_info(&mount.target).await {
+            Ok(mut status) => {
+                status.path = path.to_string();
+                status
+            }
+            Err(e) => {
+                log::warn!("Mount point {} -> {}: {}", path, &mount.target, e);
+                internal_dir_status(path)
+            }
+        }
+    }
+
+    //linkFallback中与虚拟目录同名的目录下的内容
+    async fn fallback_children(&self, dir: &str) -> Vec<FileStatus> {
+        let Some(fallback) = &self.0.fallback else {
+            return vec![];
+        };
+        let target = join(&fallback.target, dir);
+        match fallback.client.list_status(&target, false).await {
+            Ok(statuses) => statuses
+                .into_iter()
+                .map(|s| fallback.view_status(s))
+                .collect(),
+            Err(e) => {
+                log::debug!("Fallback listing of {}: {}", target, e);
+                vec![]
+            }
+        }
+    }
+
+    pub fn get_file_info<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<FileStatus, String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount
+                    .client
+                    .get_file_info(&target)
+                    .await
+                    .map(|s| mount.view_status(s)),
+                Resolved::Internal(dir) => Ok(internal_dir_status(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    //虚拟目录列出挂载点和linkFallback中的内容,同名时挂载点优先
+    pub fn list_status<'a>(
+        &'a self,
+        path: &'a str,
+        recursive: bool,
+    ) -> BoxFuture<'a, Result<Vec<FileStatus>, String>> {
+        async move {
+            if recursive {
+                let mut statuses = vec![];
+                let mut iter = self.list_status_iter(path, true);
+                while let Some(status) = iter.next().await {
+                    statuses.push(status?);
+                }
+                return Ok(statuses);
+            }
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount
+                    .client
+                    .list_status(&target, false)
+                    .await
+                    .map(|statuses| statuses.into_iter().map(|s| mount.view_status(s)).collect()),
+                Resolved::Internal(dir) => {
+                    let mut entries: BTreeMap<String, FileStatus> = self
+                        .fallback_children(&dir)
+                        .await
+                        .into_iter()
+                        .map(|s| (s.path.clone(), s))
+                        .collect();
+                    for child in self.internal_children(&dir) {
+                        let status = self.mount_point_status(&child).await;
+                        entries.insert(child, status);
+                    }
+                    Ok(entries.into_values().collect())
+                }
+            }
+        }
+        .boxed()
+    }
+
+    pub fn list_status_iter(&self, path: &str, recursive: bool) -> ViewFsListIterator {
+        ViewFsListIterator {
+            client: self.clone(),
+            recursive,
+            pending_dirs: VecDeque::from([path.to_string()]),
+            statuses: VecDeque::new(),
+        }
+    }
+
+    //虚拟目录的摘要为其下全部内容之和
+    pub fn get_content_summary<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> BoxFuture<'a, Result<ContentSummary, String>> {
+        async move {
+            let dir = match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    return mount.client.get_content_summary(&target).await;
+                }
+                Resolved::Internal(dir) => dir,
+            };
+            let mut summary = ContentSummary {
+                length: 0,
+                file_count: 0,
+                directory_count: 1,
+                quota: u64::MAX,
+                space_consumed: 0,
+                space_quota: u64::MAX,
+            };
+            for child in self.list_status(&dir, false).await? {
+                if !child.isdir {
+                    summary.length += child.length as u64;
+                    summary.file_count += 1;
+                    continue;
+                }
+                let child_summary = self.get_content_summary(&child.path).await?;
+                summary.length += child_summary.length;
+                summary.file_count += child_summary.file_count;
+                summary.directory_count += child_summary.directory_count;
+                summary.space_consumed += child_summary.space_consumed;
+            }
+            Ok(summary)
+        }
+        .boxed()
+    }
+
+    pub fn read<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<HdfsFileReader, String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount.client.read(&target).await,
+                Resolved::Internal(dir) => Err(format!("{} is a directory", dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn create<'a>(
+        &'a self,
+        path: &'a str,
+        options: WriteOptions,
+    ) -> BoxFuture<'a, Result<HdfsFileWriter, String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount.client.create(&target, options).await,
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn append<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<HdfsFileWriter, String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount.client.append(&target).await,
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    //虚拟目录已存在,mkdirs直接成功
+    pub fn mkdirs<'a>(
+        &'a self,
+        path: &'a str,
+        permission: u32,
+        create_parent: bool,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    mount
+                        .client
+                        .mkdirs(&target, permission, create_parent)
+                        .await
+                }
+                Resolved::Internal(_) => Ok(()),
+            }
+        }
+        .boxed()
+    }
+
+    //只能在同一个挂载点内改名
+    pub fn rename<'a>(
+        &'a self,
+        src: &'a str,
+        dst: &'a str,
+        overwrite: bool,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match (self.resolve(src)?, self.resolve(dst)?) {
+                (
+                    Resolved::Target(src_mount, src_target),
+                    Resolved::Target(dst_mount, dst_target),
+                ) if std::ptr::eq(src_mount, dst_mount) => {
+                    src_mount
+                        .client
+                        .rename(&src_target, &dst_target, overwrite)
+                        .await
+                }
+                (Resolved::Target(..), Resolved::Target(..)) => Err(format!(
+                    "Renames across mount points are not supported: {} -> {}",
+                    src, dst
+                )),
+                (Resolved::Internal(dir), _) | (_, Resolved::Internal(dir)) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn delete<'a>(
+        &'a self,
+        path: &'a str,
+        recursive: bool,
+    ) -> BoxFuture<'a, Result<bool, String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) if target != mount.target => {
+                    mount.client.delete(&target, recursive).await
+                }
+                //不能删除挂载点本身
+                Resolved::Target(..) => Err(read_only(path)),
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn set_permission<'a>(
+        &'a self,
+        path: &'a str,
+        permission: u32,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    mount.client.set_permission(&target, permission).await
+                }
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn set_owner<'a>(
+        &'a self,
+        path: &'a str,
+        owner: Option<&'a str>,
+        group: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    mount.client.set_owner(&target, owner, group).await
+                }
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn set_times<'a>(
+        &'a self,
+        path: &'a str,
+        mtime: Option<u64>,
+        atime: Option<u64>,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    mount.client.set_times(&target, mtime, atime).await
+                }
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn get_acl_status<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<AclStatus, String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount.client.get_acl_status(&target).await,
+                Resolved::Internal(_) => Ok(AclStatus {
+                    owner: String::new(),
+                    group: String::new(),
+                    sticky: false,
+                    permission: INTERNAL_DIR_PERMISSION,
+                    entries: vec![],
+                }),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn modify_acl_entries<'a>(
+        &'a self,
+        path: &'a str,
+        entries: Vec<AclEntry>,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    mount.client.modify_acl_entries(&target, entries).await
+                }
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn remove_acl_entries<'a>(
+        &'a self,
+        path: &'a str,
+        entries: Vec<AclEntry>,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => {
+                    mount.client.remove_acl_entries(&target, entries).await
+                }
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn remove_default_acl<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount.client.remove_default_acl(&target).await,
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    pub fn remove_acl<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) => mount.client.remove_acl(&target).await,
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+
+    //挂载点内的文件在目标文件系统内移入其回收站,namenode的回收站清理线程才会清理
+    pub fn move_to_trash<'a>(
+        &'a self,
+        path: &'a str,
+        user: &'a str,
+    ) -> BoxFuture<'a, Result<(), String>> {
+        async move {
+            match self.resolve(path)? {
+                Resolved::Target(mount, target) if target != mount.target => {
+                    mount.client.move_to_trash(&target, user).await
+                }
+                //不能删除挂载点本身
+                Resolved::Target(..) => Err(read_only(path)),
+                Resolved::Internal(dir) => Err(read_only(&dir)),
+            }
+        }
+        .boxed()
+    }
+}
+
+//逐个目录列出文件,recursive时继续列出子目录(包括挂载点)
+pub struct ViewFsListIterator {
+    client: ViewFsClient,
+    recursive: bool,
+    pending_dirs: VecDeque<String>,
+    statuses: VecDeque<FileStatus>,
+}
+
+impl ViewFsListIterator {
+    pub async fn next(&mut self) -> Option<Result<FileStatus, String>> {
+        while self.statuses.is_empty() {
+            let dir = self.pending_dirs.pop_front()?;
+            match self.client.list_status(&dir, false).await {
+                Ok(statuses) => self.statuses.extend(statuses),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let status = self.statuses.pop_front()?;
+        if self.recursive && status.isdir {
+            self.pending_dirs.push_back(status.path.clone());
+        }
+        Some(Ok(status))
+    }
+}
@@ -0,0 +1,1262 @@
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::SinkExt;
+use futures::channel::mpsc;
+use hdfs_native::acl::AclEntry;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use serde::Deserialize;
+
+use super::{AclStatus, ContentSummary, FileStatus, is_file_not_found};
+use crate::kerberos::spnego;
+
+//REST接口路径前缀
+const WEBHDFS_PREFIX: &str = "/webhdfs/v1";
+//配置了该项时使用委托令牌认证,属于敏感配置会加密保存
+pub const DELEGATION_TOKEN_KEY: &str = "hdfs-gui.webhdfs.delegation.token";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+//写入时排队等待发送的数据块个数
+const WRITE_QUEUE_SIZE: usize = 8;
+//SPNEGO认证成功后服务端返回的cookie,之后的请求不再重新认证
+const AUTH_COOKIE: &str = "hadoop.auth";
+
+//认证方式
+pub enum WebHdfsAuth {
+    //simple认证,user.name参数
+    Simple(String),
+    //SPNEGO,使用连接的票据缓存
+    Kerberos(Option<OsString>),
+    //委托令牌,delegation参数
+    Token(String),
+}
+
+//向namenode发送请求的错误
+enum SendError {
+    //没有连上namenode或SPNEGO协商失败,请求没有发出,可以换下一个namenode
+    NotSent(String),
+    //请求已发出后失败,namenode可能已经执行
+    Sent(String),
+}
+
+impl From<reqwest::Error> for SendError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_connect() {
+            SendError::NotSent(e.to_string())
+        } else {
+            SendError::Sent(e.to_string())
+        }
+    }
+}
+
+struct WebHdfsInner {
+    http: reqwest::Client,
+    //每个namenode(或HttpFS网关)一个,形如http://host:9870
+    endpoints: Vec<String>,
+    //最近一次成功的endpoint
+    current: AtomicUsize,
+    auth: WebHdfsAuth,
+    //代理用户
+    doas: Option<String>,
+    auth_cookie: Mutex<Option<String>>,
+}
+
+//WebHDFS/HttpFS客户端,可以clone后在多个任务中使用
+#[derive(Clone)]
+pub struct WebHdfsClient(Arc<WebHdfsInner>);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebHdfsFileStatus {
+    #[serde(default)]
+    access_time: u64,
+    #[serde(default)]
+    group: String,
+    #[serde(default)]
+    length: u64,
+    #[serde(default)]
+    modification_time: u64,
+    #[serde(default)]
+    owner: String,
+    #[serde(default)]
+    path_suffix: String,
+    #[serde(default)]
+    permission: String,
+    #[serde(rename = "type")]
+    file_type: String,
+}
+
+#[derive(Deserialize)]
+struct FileStatusBody {
+    #[serde(rename = "FileStatus")]
+    file_status: WebHdfsFileStatus,
+}
+
+#[derive(Deserialize)]
+struct FileStatuses {
+    #[serde(rename = "FileStatus")]
+    file_status: Vec<WebHdfsFileStatus>,
+}
+
+#[derive(Deserialize)]
+struct ListStatusBody {
+    #[serde(rename = "FileStatuses")]
+    file_statuses: FileStatuses,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebHdfsContentSummary {
+    directory_count: u64,
+    file_count: u64,
+    length: u64,
+    quota: i64,
+    space_consumed: u64,
+    space_quota: i64,
+}
+
+#[derive(Deserialize)]
+struct ContentSummaryBody {
+    #[serde(rename = "ContentSummary")]
+    content_summary: WebHdfsContentSummary,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebHdfsAclStatus {
+    #[serde(default)]
+    entries: Vec<String>,
+    #[serde(default)]
+    group: String,
+    #[serde(default)]
+    owner: String,
+    //hadoop 2.7之前没有该字段
+    #[serde(default)]
+    permission: String,
+    #[serde(default)]
+    sticky_bit: bool,
+}
+
+#[derive(Deserialize)]
+struct AclStatusBody {
+    #[serde(rename = "AclStatus")]
+    acl_status: WebHdfsAclStatus,
+}
+
+#[derive(Deserialize)]
+struct BooleanBody {
+    boolean: bool,
+}
+
+#[derive(Deserialize)]
+struct RemoteException {
+    exception: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteExceptionBody {
+    #[serde(rename = "RemoteException")]
+    remote_exception: RemoteException,
+}
+
+fn parse_permission(permission: &str) -> u16 {
+    u16::from_str_radix(permission, 8).unwrap_or_default()
+}
+
+fn join_path(parent: &str, name: &str) -> String {
+    if name.is_empty() {
+        return parent.to_string();
+    }
+    format!("{}/{}", parent.trim_end_matches('/'), name)
+}
+
+impl WebHdfsFileStatus {
+    fn into_file_status(self, parent: &str) -> FileStatus {
+        FileStatus {
+            path: join_path(parent, &self.path_suffix),
+            length: self.length as usize,
+            isdir: self.file_type == "DIRECTORY",
+            permission: parse_permission(&self.permission),
+            owner: self.owner,
+            group: self.group,
+            modification_time: self.modification_time,
+            access_time: self.access_time,
+        }
+    }
+}
+
+//acl条目与aclspec互转,形如default:user:bob:rwx
+fn acl_spec(entry: &AclEntry, with_permissions: bool) -> String {
+    let mut parts = vec![];
+    if entry.scope.to_string() == "default" {
+        parts.push("default".to_string());
+    }
+    parts.push(entry.r#type.to_string());
+    parts.push(entry.name.clone().unwrap_or_default());
+    if with_permissions {
+        parts.push(entry.permissions.to_string());
+    }
+    parts.join(":")
+}
+
+fn parse_acl_spec(spec: &str) -> Result<AclEntry, String> {
+    let (scope, rest) = match spec.strip_prefix("default:") {
+        Some(rest) => ("default", rest),
+        None => ("access", spec),
+    };
+    let parts: Vec<&str> = rest.split(':').collect();
+    let [rtype, name, permissions] = parts[..] else {
+        return Err(format!("Invalid acl entry {}", spec));
+    };
+    let name = Some(name.to_string()).filter(|n| !n.is_empty());
+    Ok(AclEntry::new(
+        rtype.to_string(),
+        scope.to_string(),
+        permissions.to_string(),
+        name,
+    ))
+}
+
+//非2xx/3xx响应转成错误,优先使用RemoteException中的异常和消息
+async fn check_response(response: Response) -> Result<Response, String> {
+    let status = response.status();
+    if status.is_success() || status.is_redirection() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    match serde_json::from_str::<RemoteExceptionBody>(&body) {
+        Ok(e) => Err(format!(
+            "{}: {}",
+            e.remote_exception.exception, e.remote_exception.message
+        )),
+        Err(_) => Err(format!("{}: {}", status, body.trim())),
+    }
+}
+
+//namenode重定向到datanode(或HttpFS重定向到data=true)时的地址
+fn redirect_location(response: &Response) -> Option<Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+impl WebHdfsClient {
+    pub fn new(
+        endpoints: Vec<String>,
+        auth: WebHdfsAuth,
+        doas: Option<String>,
+    ) -> Result<Self, String> {
+        if endpoints.is_empty() {
+            return Err("No WebHDFS endpoint configured".to_string());
+        }
+        //重定向由这里处理,以便写入时把数据发给datanode
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(WebHdfsClient(Arc::new(WebHdfsInner {
+            http,
+            endpoints,
+            current: AtomicUsize::new(0),
+            auth,
+            doas,
+            auth_cookie: Mutex::new(None),
+        })))
+    }
+
+    fn url(
+        &self,
+        endpoint: &str,
+        path: &str,
+        op: &str,
+        params: &[(&str, String)],
+    ) -> Result<Url, String> {
+        let mut url =
+            Url::parse(endpoint).map_err(|e| format!("Invalid endpoint {}: {}", endpoint, e))?;
+        url.set_path(WEBHDFS_PREFIX);
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| format!("Invalid endpoint {}", endpoint))?;
+            let names: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+            if names.is_empty() {
+                //根目录为/webhdfs/v1/
+                segments.push("");
+            }
+            segments.extend(names);
+        }
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("op", op);
+            for (key, value) in params {
+                query.append_pair(key, value);
+            }
+            match &self.0.auth {
+                WebHdfsAuth::Simple(user) => {
+                    query.append_pair("user.name", user);
+                }
+                WebHdfsAuth::Token(token) => {
+                    query.append_pair("delegation", token);
+                }
+                WebHdfsAuth::Kerberos(_) => {}
+            }
+            if let Some(doas) = &self.0.doas {
+                query.append_pair("doas", doas);
+            }
+        }
+        Ok(url)
+    }
+
+    fn auth_cookie(&self) -> Option<String> {
+        self.0.auth_cookie.lock().ok().and_then(|c| c.clone())
+    }
+
+    fn save_auth_cookie(&self, response: &Response) {
+        let cookie = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| v.split(';').next())
+            .find(|v| v.starts_with(AUTH_COOKIE));
+        let Some(cookie) = cookie else {
+            return;
+        };
+        //空值表示服务端清除了cookie
+        let value = cookie[AUTH_COOKIE.len()..].trim_start_matches('=');
+        let cookie = Some(cookie.to_string()).filter(|_| !value.is_empty() && value != "\"\"");
+        if let Ok(mut auth_cookie) = self.0.auth_cookie.lock() {
+            *auth_cookie = cookie;
+        }
+    }
+
+    async fn negotiate(&self, url: &Url, ccache: &Option<OsString>) -> Result<String, String> {
+        let host = url.host_str().unwrap_or_default().to_string();
+        let ccache = ccache.clone();
+        let token =
+            tokio::task::spawn_blocking(move || spnego::negotiate_token(&host, ccache.as_deref()))
+                .await
+                .map_err(|e| e.to_string())??;
+        Ok(format!("Negotiate {}", token))
+    }
+
+    //带认证信息发送请求,SPNEGO时优先使用cookie,cookie过期后重新协商一次
+    async fn send_to(&self, method: Method, url: Url) -> Result<Response, SendError> {
+        let WebHdfsAuth::Kerberos(ccache) = &self.0.auth else {
+            return Ok(self.0.http.request(method, url).send().await?);
+        };
+        if let Some(cookie) = self.auth_cookie() {
+            let response = self
+                .0
+                .http
+                .request(method.clone(), url.clone())
+                .header(COOKIE, cookie)
+                .send()
+                .await?;
+            if response.status() != StatusCode::UNAUTHORIZED {
+                return Ok(response);
+            }
+        }
+        let negotiate = self
+            .negotiate(&url, ccache)
+            .await
+            .map_err(SendError::NotSent)?;
+        let response = self
+            .0
+            .http
+            .request(method, url.clone())
+            .header(AUTHORIZATION, negotiate)
+            .send()
+            .await?;
+        self.save_auth_cookie(&response);
+        Ok(response)
+    }
+
+    //向namenode发送请求,HA时遇到连接失败或StandbyException换下一个namenode。
+    //请求发出后的其他错误不重试,RENAME、DELETE、APPEND等操作不是幂等的
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        op: &str,
+        params: &[(&str, String)],
+    ) -> Result<Response, String> {
+        let count = self.0.endpoints.len();
+        let start = self.0.current.load(Ordering::Relaxed);
+        let mut errors = vec![];
+        for attempt in 0..count {
+            let index = (start + attempt) % count;
+            let endpoint = &self.0.endpoints[index];
+            let url = self.url(endpoint, path, op, params)?;
+            let response = match self.send_to(method.clone(), url).await {
+                Ok(response) => check_response(response).await,
+                Err(SendError::NotSent(e)) => {
+                    errors.push(format!("{}: {}", endpoint, e));
+                    continue;
+                }
+                Err(SendError::Sent(e)) => {
+                    errors.push(format!("{}: {}", endpoint, e));
+                    return Err(errors.join("; "));
+                }
+            };
+            match response {
+                Err(e) if count > 1 && e.starts_with("StandbyException") => {
+                    errors.push(format!("{}: {}", endpoint, e));
+                }
+                Err(e) => return Err(e),
+                Ok(response) => {
+                    self.0.current.store(index, Ordering::Relaxed);
+                    return Ok(response);
+                }
+            }
+        }
+        Err(errors.join("; "))
+    }
+
+    async fn send_json<T: serde::de::DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        op: &str,
+        params: &[(&str, String)],
+    ) -> Result<T, String> {
+        self.send(method, path, op, params)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(|e| format!("{} {}: {}", op, path, e))
+    }
+
+    //跟随重定向,datanode地址中已带委托令牌,HttpFS需要带上认证cookie
+    fn follow(&self, method: Method, location: Url) -> RequestBuilder {
+        let request = self.0.http.request(method, location);
+        match self.auth_cookie() {
+            Some(cookie) => request.header(COOKIE, cookie),
+            None => request,
+        }
+    }
+
+    pub async fn get_file_info(&self, path: &str) -> Result<FileStatus, String> {
+        let body: FileStatusBody = self
+            .send_json(Method::GET, path, "GETFILESTATUS", &[])
+            .await?;
+        Ok(body.file_status.into_file_status(path))
+    }
+
+    pub async fn list_status(&self, path: &str) -> Result<Vec<FileStatus>, String> {
+        let body: ListStatusBody = self.send_json(Method::GET, path, "LISTSTATUS", &[]).await?;
+        Ok(body
+            .file_statuses
+            .file_status
+            .into_iter()
+            .map(|s| s.into_file_status(path))
+            .collect())
+    }
+
+    pub fn list_status_iter(&self, path: &str, recursive: bool) -> WebHdfsListIterator {
+        WebHdfsListIterator {
+            client: self.clone(),
+            recursive,
+            pending_dirs: VecDeque::from([path.to_string()]),
+            statuses: VecDeque::new(),
+        }
+    }
+
+    pub async fn get_content_summary(&self, path: &str) -> Result<ContentSummary, String> {
+        let body: ContentSummaryBody = self
+            .send_json(Method::GET, path, "GETCONTENTSUMMARY", &[])
+            .await?;
+        let summary = body.content_summary;
+        Ok(ContentSummary {
+            length: summary.length,
+            file_count: summary.file_count,
+            directory_count: summary.directory_count,
+            //-1表示未设置,与rpc返回的值一致
+            quota: summary.quota as u64,
+            space_consumed: summary.space_consumed,
+            space_quota: summary.space_quota as u64,
+        })
+    }
+
+    pub async fn read(&self, path: &str) -> Result<WebHdfsReader, String> {
+        let status = self.get_file_info(path).await?;
+        if status.isdir {
+            return Err(format!("{} is a directory", path));
+        }
+        Ok(WebHdfsReader {
+            client: self.clone(),
+            path: path.to_string(),
+            length: status.length,
+            position: 0,
+        })
+    }
+
+    async fn read_range(&self, path: &str, offset: usize, length: usize) -> Result<Bytes, String> {
+        if length == 0 {
+            return Ok(Bytes::new());
+        }
+        let params = [
+            ("offset", offset.to_string()),
+            ("length", length.to_string()),
+        ];
+        let response = self.send(Method::GET, path, "OPEN", &params).await?;
+        let response = match redirect_location(&response) {
+            Some(location) => {
+                let response = self
+                    .follow(Method::GET, location)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                check_response(response).await?
+            }
+            None => response,
+        };
+        response.bytes().await.map_err(|e| e.to_string())
+    }
+
+    //先向namenode请求写入地址,再把数据流式发送到重定向的datanode
+    async fn open_writer(
+        &self,
+        method: Method,
+        path: &str,
+        op: &str,
+        params: &[(&str, String)],
+    ) -> Result<WebHdfsWriter, String> {
+        let response = self.send(method.clone(), path, op, params).await?;
+        let location = redirect_location(&response)
+            .ok_or_else(|| format!("{} {} was not redirected to a datanode", op, path))?;
+        let (sender, receiver) = mpsc::channel::<Result<Bytes, std::io::Error>>(WRITE_QUEUE_SIZE);
+        let request = self
+            .follow(method, location)
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .body(reqwest::Body::wrap_stream(receiver));
+        let task = tokio::spawn(async move {
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            check_response(response).await.map(|_| ())
+        });
+        //新建的文件在写入中断时删除,追加时保留原有内容
+        let created = Some((self.clone(), path.to_string())).filter(|_| op == "CREATE");
+        Ok(WebHdfsWriter {
+            sender,
+            task: Some(task),
+            created,
+        })
+    }
+
+    pub async fn create(
+        &self,
+        path: &str,
+        options: &hdfs_native::WriteOptions,
+    ) -> Result<WebHdfsWriter, String> {
+        if !options.create_parent {
+            self.check_parent(path).await?;
+        }
+        let mut params = vec![
+            ("overwrite", options.overwrite.to_string()),
+            ("permission", format!("{:o}", options.permission)),
+        ];
+        if let Some(block_size) = options.block_size {
+            params.push(("blocksize", block_size.to_string()));
+        }
+        if let Some(replication) = options.replication {
+            params.push(("replication", replication.to_string()));
+        }
+        self.open_writer(Method::PUT, path, "CREATE", &params).await
+    }
+
+    pub async fn append(&self, path: &str) -> Result<WebHdfsWriter, String> {
+        self.open_writer(Method::POST, path, "APPEND", &[]).await
+    }
+
+    //WebHDFS总是创建上级目录,不允许时先检查上级目录是否存在
+    async fn check_parent(&self, path: &str) -> Result<(), String> {
+        let parent = std::path::Path::new(path)
+            .parent()
+            .map_or("/", |p| p.to_str().unwrap_or("/"));
+        match self.get_file_info(parent).await {
+            Ok(status) if status.isdir => Ok(()),
+            Ok(_) => Err(format!("Parent path is not a directory: {}", parent)),
+            Err(e) if is_file_not_found(&e) => {
+                Err(format!("Parent directory doesn't exist: {}", parent))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn mkdirs(
+        &self,
+        path: &str,
+        permission: u32,
+        create_parent: bool,
+    ) -> Result<(), String> {
+        if !create_parent {
+            self.check_parent(path).await?;
+        }
+        let params = [("permission", format!("{:o}", permission))];
+        let body: BooleanBody = self.send_json(Method::PUT, path, "MKDIRS", &params).await?;
+        if !body.boolean {
+            return Err(format!("Failed to create directory {}", path));
+        }
+        Ok(())
+    }
+
+    pub async fn rename(&self, src: &str, dst: &str, overwrite: bool) -> Result<(), String> {
+        let mut params = vec![("destination", dst.to_string())];
+        //带renameoptions时服务端使用rename2,失败时返回异常而不是false
+        if overwrite {
+            params.push(("renameoptions", "OVERWRITE".to_string()));
+            self.send(Method::PUT, src, "RENAME", &params).await?;
+            return Ok(());
+        }
+        let body: BooleanBody = self.send_json(Method::PUT, src, "RENAME", &params).await?;
+        if !body.boolean {
+            return Err(format!("Failed to rename {} to {}", src, dst));
+        }
+        Ok(())
+    }
+
+    pub async fn delete(&self, path: &str, recursive: bool) -> Result<bool, String> {
+        let params = [("recursive", recursive.to_string())];
+        let body: BooleanBody = self
+            .send_json(Method::DELETE, path, "DELETE", &params)
+            .await?;
+        Ok(body.boolean)
+    }
+
+    pub async fn set_permission(&self, path: &str, permission: u32) -> Result<(), String> {
+        let params = [("permission", format!("{:o}", permission))];
+        self.send(Method::PUT, path, "SETPERMISSION", &params)
+            .await
+            .map(|_| ())
+    }
+
+    //未传的所有者或组不修改
+    pub async fn set_owner(
+        &self,
+        path: &str,
+        owner: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<(), String> {
+        let params: Vec<(&str, String)> = [("owner", owner), ("group", group)]
+            .into_iter()
+            .filter_map(|(key, name)| name.map(|name| (key, name.to_string())))
+            .collect();
+        self.send(Method::PUT, path, "SETOWNER", &params)
+            .await
+            .map(|_| ())
+    }
+
+    //未传的时间不修改
+    pub async fn set_times(
+        &self,
+        path: &str,
+        mtime: Option<u64>,
+        atime: Option<u64>,
+    ) -> Result<(), String> {
+        let params: Vec<(&str, String)> = [("modificationtime", mtime), ("accesstime", atime)]
+            .into_iter()
+            .filter_map(|(key, time)| time.map(|time| (key, time.to_string())))
+            .collect();
+        self.send(Method::PUT, path, "SETTIMES", &params)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn get_acl_status(&self, path: &str) -> Result<AclStatus, String> {
+        let body: AclStatusBody = self
+            .send_json(Method::GET, path, "GETACLSTATUS", &[])
+            .await?;
+        let acl_status = body.acl_status;
+        Ok(AclStatus {
+            owner: acl_status.owner,
+            group: acl_status.group,
+            sticky: acl_status.sticky_bit,
+            permission: parse_permission(&acl_status.permission),
+            entries: acl_status
+                .entries
+                .iter()
+                .map(|spec| parse_acl_spec(spec))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    async fn set_acl_entries(
+        &self,
+        path: &str,
+        op: &str,
+        entries: &[AclEntry],
+        with_permissions: bool,
+    ) -> Result<(), String> {
+        let spec = entries
+            .iter()
+            .map(|e| acl_spec(e, with_permissions))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.send(Method::PUT, path, op, &[("aclspec", spec)])
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn modify_acl_entries(&self, path: &str, entries: &[AclEntry]) -> Result<(), String> {
+        self.set_acl_entries(path, "MODIFYACLENTRIES", entries, true)
+            .await
+    }
+
+    //删除时aclspec不带权限
+    pub async fn remove_acl_entries(&self, path: &str, entries: &[AclEntry]) -> Result<(), String> {
+        self.set_acl_entries(path, "REMOVEACLENTRIES", entries, false)
+            .await
+    }
+
+    pub async fn remove_default_acl(&self, path: &str) -> Result<(), String> {
+        self.send(Method::PUT, path, "REMOVEDEFAULTACL", &[])
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn remove_acl(&self, path: &str) -> Result<(), String> {
+        self.send(Method::PUT, path, "REMOVEACL", &[])
+            .await
+            .map(|_| ())
+    }
+}
+
+//按OPEN的offset/length读取文件
+pub struct WebHdfsReader {
+    client: WebHdfsClient,
+    path: String,
+    length: usize,
+    position: usize,
+}
+
+impl WebHdfsReader {
+    pub fn file_length(&self) -> usize {
+        self.length
+    }
+
+    //从当前位置读取最多len字节,读到末尾时返回空
+    pub async fn read(&mut self, len: usize) -> Result<Bytes, String> {
+        let len = len.min(self.length.saturating_sub(self.position));
+        let bytes = self.read_range(self.position, len).await?;
+        self.position += bytes.len();
+        Ok(bytes)
+    }
+
+    pub async fn read_range(&self, offset: usize, len: usize) -> Result<Bytes, String> {
+        self.client.read_range(&self.path, offset, len).await
+    }
+
+    pub async fn read_range_buf(&self, buf: &mut [u8], offset: usize) -> Result<(), String> {
+        let bytes = self.read_range(offset, buf.len()).await?;
+        if bytes.len() != buf.len() {
+            return Err(format!(
+                "Short read of {} at {}: {} of {} bytes",
+                self.path,
+                offset,
+                bytes.len(),
+                buf.len()
+            ));
+        }
+        buf.copy_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+//写入的数据通过通道交给后台请求,close时等待datanode返回结果
+pub struct WebHdfsWriter {
+    sender: mpsc::Sender<Result<Bytes, std::io::Error>>,
+    task: Option<tokio::task::JoinHandle<Result<(), String>>>,
+    //CREATE时的客户端和路径
+    created: Option<(WebHdfsClient, String)>,
+}
+
+//没有close就丢弃时(如取消上传)中断请求,否则请求体正常结束,datanode会把已收到的部分
+//当作完整文件。datanode仍会关闭已写入的部分,新建的文件在请求结束后删除
+impl Drop for WebHdfsWriter {
+    fn drop(&mut self) {
+        let Some(task) = self.task.take() else {
+            return;
+        };
+        let aborted = std::io::Error::other("WebHDFS writer dropped before close");
+        if self.sender.try_send(Err(aborted)).is_err() {
+            task.abort();
+        }
+        let Some((client, path)) = self.created.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        runtime.spawn(async move {
+            let _ = task.await;
+            match client.delete(&path, false).await {
+                Ok(_) => log::info!("Removed unfinished WebHDFS file {}", path),
+                Err(e) => log::warn!("Failed to remove unfinished WebHDFS file {}: {}", path, e),
+            }
+        });
+    }
+}
+
+impl WebHdfsWriter {
+    async fn finish(&mut self) -> Result<(), String> {
+        self.sender.close_channel();
+        match self.task.take() {
+            Some(task) => task.await.map_err(|e| e.to_string())?,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn write(&mut self, buf: Bytes) -> Result<usize, String> {
+        let len = buf.len();
+        if self.sender.send(Ok(buf)).await.is_err() {
+            //请求已提前结束,返回它的错误
+            self.finish().await?;
+            return Err("WebHDFS write ended before all data was sent".to_string());
+        }
+        Ok(len)
+    }
+
+    pub async fn close(&mut self) -> Result<(), String> {
+        self.finish().await
+    }
+}
+
+//逐个目录列出文件,recursive时继续列出子目录
+pub struct WebHdfsListIterator {
+    client: WebHdfsClient,
+    recursive: bool,
+    pending_dirs: VecDeque<String>,
+    statuses: VecDeque<FileStatus>,
+}
+
+impl WebHdfsListIterator {
+    pub async fn next(&mut self) -> Option<Result<FileStatus, String>> {
+        while self.statuses.is_empty() {
+            let dir = self.pending_dirs.pop_front()?;
+            match self.client.list_status(&dir).await {
+                Ok(statuses) => self.statuses.extend(statuses),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let status = self.statuses.pop_front()?;
+        if self.recursive && status.isdir {
+            self.pending_dirs.push_back(status.path.clone());
+        }
+        Some(Ok(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::{WebHdfsAuth, WebHdfsClient};
+    use crate::backend::is_file_not_found;
+
+    //mock收到的请求,complete为请求体是否完整
+    #[derive(Debug, Clone)]
+    struct MockRequest {
+        method: String,
+        target: String,
+        body: Vec<u8>,
+        complete: bool,
+    }
+
+    impl MockRequest {
+        fn op(&self) -> &str {
+            self.target
+                .split(['?', '&'])
+                .find_map(|p| p.strip_prefix("op="))
+                .unwrap_or_default()
+        }
+    }
+
+    //状态码、额外的响应头和响应体
+    type MockResponse = (u16, Vec<(&'static str, String)>, String);
+    type Handler = Arc<dyn Fn(&MockRequest, &str) -> MockResponse + Send + Sync>;
+
+    struct MockServer {
+        url: String,
+        requests: Arc<Mutex<Vec<MockRequest>>>,
+    }
+
+    impl MockServer {
+        //handler的第二个参数为mock自己的地址,用于重定向
+        async fn start(
+            handler: impl Fn(&MockRequest, &str) -> MockResponse + Send + Sync + 'static,
+        ) -> MockServer {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(vec![]));
+            let handler: Handler = Arc::new(handler);
+            let server_url = url.clone();
+            let server_requests = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let handler = handler.clone();
+                    let url = server_url.clone();
+                    let requests = server_requests.clone();
+                    tokio::spawn(serve(stream, handler, url, requests));
+                }
+            });
+            MockServer { url, requests }
+        }
+
+        fn requests(&self) -> Vec<MockRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    //每个连接只处理一个请求
+    async fn serve(
+        stream: TcpStream,
+        handler: Handler,
+        url: String,
+        requests: Arc<Mutex<Vec<MockRequest>>>,
+    ) {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default().to_string();
+        let mut content_length = 0;
+        let mut chunked = false;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let (name, value) = header.split_once(':').unwrap_or((header, ""));
+            match name.to_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "transfer-encoding" => chunked = value.trim().eq_ignore_ascii_case("chunked"),
+                _ => {}
+            }
+        }
+        let (body, complete) = if chunked {
+            read_chunked(&mut reader).await
+        } else {
+            let mut body = vec![0; content_length];
+            let complete = reader.read_exact(&mut body).await.is_ok();
+            (body, complete)
+        };
+        let request = MockRequest {
+            method,
+            target,
+            body,
+            complete,
+        };
+        requests.lock().unwrap().push(request.clone());
+        if !request.complete {
+            return;
+        }
+        let (status, headers, body) = handler(&request, &url);
+        let mut response = format!(
+            "HTTP/1.1 {} MOCK\r\nContent-Length: {}\r\nConnection: close\r\n",
+            status,
+            body.len()
+        );
+        for (name, value) in headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+        response.push_str(&body);
+        let mut stream = reader.into_inner();
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
+    //读取chunked请求体,连接在结束块之前关闭时不完整
+    async fn read_chunked(reader: &mut BufReader<TcpStream>) -> (Vec<u8>, bool) {
+        let mut body = vec![];
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return (body, false);
+            }
+            let Ok(size) = usize::from_str_radix(line.trim(), 16) else {
+                return (body, false);
+            };
+            let mut chunk = vec![0; size + 2];
+            if reader.read_exact(&mut chunk).await.is_err() {
+                return (body, false);
+            }
+            if size == 0 {
+                return (body, true);
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    }
+
+    fn json(status: u16, body: &str) -> MockResponse {
+        (
+            status,
+            vec![("Content-Type", "application/json".to_string())],
+            body.to_string(),
+        )
+    }
+
+    fn remote_exception(status: u16, exception: &str, message: &str) -> MockResponse {
+        json(
+            status,
+            &format!(
+                r#"{{"RemoteException":{{"exception":"{}","javaClassName":"x","message":"{}"}}}}"#,
+                exception, message
+            ),
+        )
+    }
+
+    fn client(endpoints: Vec<String>) -> WebHdfsClient {
+        WebHdfsClient::new(endpoints, WebHdfsAuth::Simple("alice".to_string()), None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn parses_file_status_and_listing() {
+        let server = MockServer::start(|request, _| match request.op() {
+            "GETFILESTATUS" => json(
+                200,
+                r#"{"FileStatus":{"accessTime":1,"blockSize":134217728,"group":"hadoop",
+                    "length":12,"modificationTime":2,"owner":"alice","pathSuffix":"",
+                    "permission":"644","replication":3,"type":"FILE"}}"#,
+            ),
+            "LISTSTATUS" => json(
+                200,
+                r#"{"FileStatuses":{"FileStatus":[
+                    {"length":0,"owner":"alice","group":"hadoop","pathSuffix":"sub",
+                     "permission":"1777","type":"DIRECTORY"},
+                    {"length":5,"owner":"bob","group":"hadoop","pathSuffix":"a.txt",
+                     "permission":"600","type":"FILE","modificationTime":7}]}}"#,
+            ),
+            _ => remote_exception(400, "IllegalArgumentException", "unexpected op"),
+        })
+        .await;
+        let client = client(vec![server.url.clone()]);
+
+        let status = client.get_file_info("/data/f.txt").await.unwrap();
+        assert_eq!(status.path, "/data/f.txt");
+        assert_eq!(status.length, 12);
+        assert!(!status.isdir);
+        assert_eq!(status.permission, 0o644);
+        assert_eq!(status.owner, "alice");
+        assert_eq!((status.modification_time, status.access_time), (2, 1));
+
+        let statuses = client.list_status("/data/").await.unwrap();
+        let paths: Vec<&str> = statuses.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, ["/data/sub", "/data/a.txt"]);
+        assert!(statuses[0].isdir);
+        assert_eq!(statuses[0].permission, 0o1777);
+        assert_eq!(statuses[1].permission, 0o600);
+        assert_eq!(statuses[1].modification_time, 7);
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "GET");
+        assert!(
+            request
+                .target
+                .starts_with("/webhdfs/v1/data/f.txt?op=GETFILESTATUS")
+        );
+        assert!(request.target.contains("user.name=alice"));
+    }
+
+    #[tokio::test]
+    async fn fails_over_from_standby_namenode() {
+        let standby = MockServer::start(|_, _| {
+            remote_exception(
+                403,
+                "StandbyException",
+                "Operation category READ is not supported in state standby",
+            )
+        })
+        .await;
+        let active = MockServer::start(|_, _| {
+            json(
+                200,
+                r#"{"FileStatus":{"length":0,"pathSuffix":"","permission":"755","type":"DIRECTORY"}}"#,
+            )
+        })
+        .await;
+        let client = client(vec![standby.url.clone(), active.url.clone()]);
+
+        assert!(client.get_file_info("/").await.unwrap().isdir);
+        assert!(client.get_file_info("/").await.unwrap().isdir);
+        //第二次直接使用active namenode
+        assert_eq!(standby.requests().len(), 1);
+        assert_eq!(active.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_standby_errors_of_every_namenode() {
+        let standby =
+            MockServer::start(|_, _| remote_exception(403, "StandbyException", "standby")).await;
+        let client = client(vec![standby.url.clone(), standby.url.clone()]);
+
+        let error = client.get_file_info("/").await.unwrap_err();
+        assert_eq!(error.matches("StandbyException").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_namenode_is_unreachable() {
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+        let active = MockServer::start(|_, _| json(200, r#"{"boolean":true}"#)).await;
+        let client = client(vec![closed_url, active.url.clone()]);
+
+        client.rename("/a", "/b", false).await.unwrap();
+        assert_eq!(active.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_requests_already_sent() {
+        //读完请求后不响应直接断开
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dropped_url = format!("http://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(0));
+        let counter = received.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).await;
+                *counter.lock().unwrap() += 1;
+            }
+        });
+        let active = MockServer::start(|_, _| json(200, r#"{"boolean":true}"#)).await;
+        let client = client(vec![dropped_url, active.url.clone()]);
+
+        assert!(client.rename("/a", "/b", false).await.is_err());
+        assert_eq!(*received.lock().unwrap(), 1);
+        assert!(active.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn creates_file_through_datanode_redirect() {
+        let server = MockServer::start(|request, url| {
+            if request.target.starts_with("/datanode/") {
+                return (
+                    201,
+                    vec![("Location", "hdfs:///data/new.txt".to_string())],
+                    "".to_string(),
+                );
+            }
+            match request.op() {
+                "CREATE" => (
+                    307,
+                    vec![("Location", format!("{}/datanode/new.txt?op=CREATE", url))],
+                    "".to_string(),
+                ),
+                _ => remote_exception(400, "IllegalArgumentException", "unexpected op"),
+            }
+        })
+        .await;
+        let client = client(vec![server.url.clone()]);
+
+        let options = hdfs_native::WriteOptions::default().overwrite(true);
+        let mut writer = client.create("/data/new.txt", &options).await.unwrap();
+        writer.write(Bytes::from_static(b"hello ")).await.unwrap();
+        writer.write(Bytes::from_static(b"webhdfs")).await.unwrap();
+        writer.close().await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "PUT");
+        assert!(requests[0].target.contains("overwrite=true"));
+        assert!(requests[0].body.is_empty());
+        assert_eq!(requests[1].method, "PUT");
+        assert!(requests[1].target.starts_with("/datanode/new.txt"));
+        assert_eq!(requests[1].body, b"hello webhdfs");
+        assert!(requests[1].complete);
+    }
+
+    #[tokio::test]
+    async fn aborts_and_removes_file_when_writer_is_dropped() {
+        let server = MockServer::start(|request, url| match request.op() {
+            "CREATE" if !request.target.starts_with("/datanode/") => (
+                307,
+                vec![("Location", format!("{}/datanode/new.txt?op=CREATE", url))],
+                "".to_string(),
+            ),
+            "DELETE" => json(200, r#"{"boolean":true}"#),
+            _ => (201, vec![], "".to_string()),
+        })
+        .await;
+        let client = client(vec![server.url.clone()]);
+
+        let options = hdfs_native::WriteOptions::default();
+        let mut writer = client.create("/data/new.txt", &options).await.unwrap();
+        writer.write(Bytes::from_static(b"partial")).await.unwrap();
+        //等数据发到datanode后再丢弃
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        drop(writer);
+
+        let mut requests = vec![];
+        for _ in 0..50 {
+            requests = server.requests();
+            let uploaded = requests.iter().any(|r| r.target.starts_with("/datanode/"));
+            if uploaded && requests.iter().any(|r| r.op() == "DELETE") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let upload = requests
+            .iter()
+            .find(|r| r.target.starts_with("/datanode/"))
+            .unwrap();
+        assert!(!upload.complete);
+        let delete = requests.iter().find(|r| r.op() == "DELETE").unwrap();
+        assert!(delete.target.starts_with("/webhdfs/v1/data/new.txt?"));
+    }
+
+    #[tokio::test]
+    async fn maps_remote_exceptions() {
+        let server = MockServer::start(|request, _| match request.op() {
+            "GETFILESTATUS" => remote_exception(
+                404,
+                "FileNotFoundException",
+                "File does not exist: /missing",
+            ),
+            "MKDIRS" => remote_exception(
+                403,
+                "AccessControlException",
+                "Permission denied: user=alice, access=WRITE",
+            ),
+            _ => (500, vec![], "plain failure".to_string()),
+        })
+        .await;
+        let client = client(vec![server.url.clone()]);
+
+        let error = client.get_file_info("/missing").await.unwrap_err();
+        assert_eq!(
+            error,
+            "FileNotFoundException: File does not exist: /missing"
+        );
+        assert!(is_file_not_found(&error));
+
+        let error = client.mkdirs("/secure/dir", 0o755, true).await.unwrap_err();
+        assert!(error.starts_with("AccessControlException: Permission denied"));
+
+        let error = client.delete("/x", false).await.unwrap_err();
+        assert!(error.starts_with("500"));
+        assert!(error.ends_with("plain failure"));
+    }
+}
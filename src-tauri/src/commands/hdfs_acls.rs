@@ -27,14 +27,8 @@ pub struct HdfsAclEntry {
 pub async fn get_hdfs_file_acl_list(id: i64, file_path: String) -> Result<HdfsAcl, String> {
     //log::info!("get_hdfs_file_list:parent_path:{}", &parent_path);
     let client = get_hdfs_client(id).await?;
-    let file_status = client
-        .get_file_info(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
-    let acl_status = client
-        .get_acl_status(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let file_status = client.get_file_info(&file_path).await?;
+    let acl_status = client.get_acl_status(&file_path).await?;
     let hdfs_acls = HdfsAcl {
         owner: acl_status.owner,
         group: acl_status.group,
@@ -91,7 +85,7 @@ pub async fn add_acl(
     permissions: String,
     name: Option<String>,
 ) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
 
     log::info!(
         "add_acl:file_path:{}, rtype:{}, scope:{}, permissions:{}, name:{}",
@@ -106,8 +100,7 @@ pub async fn add_acl(
             &file_path,
             vec![AclEntry::new(rtype, scope, permissions, name)],
         )
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     return Ok(true);
 }
@@ -122,7 +115,7 @@ pub async fn delete_acl(
     permissions: String,
     name: Option<String>,
 ) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
 
     log::info!(
         "remove_acl:file_path:{}, rtype:{}, scope:{}, permissions:{}, name:{}",
@@ -137,8 +130,7 @@ pub async fn delete_acl(
             &file_path,
             vec![AclEntry::new(rtype, scope, permissions, name)],
         )
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     return Ok(true);
 }
@@ -146,13 +138,10 @@ pub async fn delete_acl(
 //删除Acl
 #[tauri::command]
 pub async fn delete_default_acl(id: i64, file_path: String) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
 
     log::info!("delete_default_acl:file_path:{}", &file_path);
-    client
-        .remove_default_acl(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    client.remove_default_acl(&file_path).await?;
 
     return Ok(true);
 }
@@ -160,13 +149,10 @@ pub async fn delete_default_acl(id: i64, file_path: String) -> Result<bool, Stri
 //删除全部Acl
 #[tauri::command]
 pub async fn delete_all_acl(id: i64, file_path: String) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
 
     log::info!("delete_all_acl:file_path:{}", &file_path);
-    client
-        .remove_acl(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    client.remove_acl(&file_path).await?;
 
     return Ok(true);
 }
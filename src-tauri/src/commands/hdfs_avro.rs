@@ -1,12 +1,14 @@
 
 use std::io::{ErrorKind, Read};
+use std::sync::Arc;
 
 use apache_avro::Reader;
 
 use super::hdfs_file::get_hdfs_client;
+use crate::backend::HdfsClient;
 
 pub struct HdfsAvroFileReader {
-    client: Box<hdfs_native::Client>,
+    client: Arc<HdfsClient>,
     file_path: String,
     offset: Box<usize>,
 }
@@ -80,7 +82,7 @@ pub async fn get_avro_reader(
 ) -> Result<Reader<'static, HdfsAvroFileReader>, String> {
     let hdfs_client = get_hdfs_client(id).await?;
     let hdfs_avro_file_reader = HdfsAvroFileReader {
-        client: Box::new(hdfs_client),
+        client: hdfs_client,
         file_path: file_path.to_string(),
         offset: Box::new(0),
     };
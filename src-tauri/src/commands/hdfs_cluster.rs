@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::header::AUTHORIZATION;
+use serde::{Deserialize, Serialize};
+
+use super::hdfs_config::get_one_hdfs_config;
+use super::hdfs_connection::{NamenodeAddress, namenode_addresses, namenode_http_address};
+
+//jmx请求超时
+const JMX_TIMEOUT: Duration = Duration::from_secs(10);
+//web接口拒绝认证时的错误前缀
+const JMX_AUTH_FAILED: &str = "Authentication failed";
+
+//单个namenode的状态
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct NamenodeStatus {
+    //HA配置中的namenode名称,非HA时为空
+    pub name: String,
+    pub rpc_address: String,
+    //jmx所在的web地址
+    pub http_address: String,
+    //active/standby/observer,无法获取时为空
+    pub state: String,
+    pub error: String,
+}
+
+//集群容量和块统计,来自active namenode
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ClusterFsStats {
+    pub capacity: i64,
+    pub used: i64,
+    pub remaining: i64,
+    pub under_replicated_blocks: i64,
+    pub corrupt_blocks: i64,
+    pub missing_blocks: i64,
+    pub live_datanodes: i64,
+    pub dead_datanodes: i64,
+}
+
+//集群状态
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ClusterStatus {
+    //没有可访问的active namenode时为空
+    pub fs_stats: Option<ClusterFsStats>,
+    pub safemode: bool,
+    //安全模式说明,不在安全模式时为空
+    pub safemode_message: String,
+    pub namenodes: Vec<NamenodeStatus>,
+    //没有active namenode且web接口拒绝了认证
+    pub auth_failed: bool,
+}
+
+//jmx请求的http客户端,kerberos连接在web接口要求认证时使用SPNEGO
+struct JmxClient {
+    http: reqwest::Client,
+    spnego: bool,
+    //kerberos连接的票据缓存
+    ccache: Option<OsString>,
+}
+
+impl JmxClient {
+    async fn negotiate(&self, url: &str) -> Result<String, String> {
+        let host = reqwest::Url::parse(url)
+            .map_err(|e| e.to_string())?
+            .host_str()
+            .unwrap_or_default()
+            .to_string();
+        let ccache = self.ccache.clone();
+        let token = tokio::task::spawn_blocking(move || {
+            crate::kerberos::spnego::negotiate_token(&host, ccache.as_deref())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        Ok(format!("Negotiate {}", token))
+    }
+
+    //先不带认证请求,返回401时用SPNEGO重试
+    async fn get(&self, url: &str) -> Result<reqwest::Response, String> {
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("{}: {}", url, e))?;
+        if response.status() != StatusCode::UNAUTHORIZED || !self.spnego {
+            return Ok(response);
+        }
+        let authorization = self
+            .negotiate(url)
+            .await
+            .map_err(|e| format!("{}: {}: {}", JMX_AUTH_FAILED, url, e))?;
+        self.http
+            .get(url)
+            .header(AUTHORIZATION, authorization)
+            .send()
+            .await
+            .map_err(|e| format!("{}: {}", url, e))
+    }
+}
+
+//读取一个jmx bean
+async fn query_jmx(
+    client: &JmxClient,
+    http_address: &str,
+    bean: &str,
+) -> Result<serde_json::Value, String> {
+    let url = format!(
+        "{}/jmx?qry=Hadoop:service=NameNode,name={}",
+        http_address, bean
+    );
+    let response = client.get(&url).await?;
+    if response.status() == StatusCode::UNAUTHORIZED || response.status() == StatusCode::FORBIDDEN {
+        return Err(format!(
+            "{}: {}: {}",
+            JMX_AUTH_FAILED,
+            url,
+            response.status()
+        ));
+    }
+    if !response.status().is_success() {
+        return Err(format!("{}: {}", url, response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("{}: {}", url, e))?;
+    body["beans"]
+        .as_array()
+        .and_then(|beans| beans.first())
+        .cloned()
+        .ok_or_else(|| format!("{}: bean {} not found", url, bean))
+}
+
+fn jmx_i64(bean: &serde_json::Value, key: &str) -> i64 {
+    bean[key].as_i64().unwrap_or_default()
+}
+
+//读取namenode的HA角色
+async fn query_namenode_state(
+    client: &JmxClient,
+    namenode: &NamenodeAddress,
+    http_address: String,
+) -> NamenodeStatus {
+    let mut status = NamenodeStatus {
+        name: namenode.name.clone(),
+        rpc_address: namenode.address.clone(),
+        http_address,
+        ..Default::default()
+    };
+    match query_jmx(client, &status.http_address, "NameNodeStatus").await {
+        Ok(bean) => status.state = bean["State"].as_str().unwrap_or_default().to_lowercase(),
+        Err(e) => status.error = e,
+    }
+    status
+}
+
+//从active namenode读取容量、块统计和安全模式
+async fn query_fs_stats(
+    client: &JmxClient,
+    http_address: &str,
+) -> Result<(ClusterFsStats, String), String> {
+    let fs = query_jmx(client, http_address, "FSNamesystem").await?;
+    let fs_state = query_jmx(client, http_address, "FSNamesystemState").await?;
+    let info = query_jmx(client, http_address, "NameNodeInfo").await?;
+    let stats = ClusterFsStats {
+        capacity: jmx_i64(&fs, "CapacityTotal"),
+        used: jmx_i64(&fs, "CapacityUsed"),
+        remaining: jmx_i64(&fs, "CapacityRemaining"),
+        under_replicated_blocks: jmx_i64(&fs, "UnderReplicatedBlocks"),
+        corrupt_blocks: jmx_i64(&fs, "CorruptBlocks"),
+        missing_blocks: jmx_i64(&fs, "MissingBlocks"),
+        live_datanodes: jmx_i64(&fs_state, "NumLiveDataNodes"),
+        dead_datanodes: jmx_i64(&fs_state, "NumDeadDataNodes"),
+    };
+    let safemode_message = info["Safemode"].as_str().unwrap_or_default().to_string();
+    Ok((stats, safemode_message))
+}
+
+//获取集群容量、块统计、安全模式和各namenode的HA角色(通过namenode的jmx接口)
+#[tauri::command]
+pub async fn get_cluster_status(id: i64) -> Result<ClusterStatus, String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let hc = get_one_hdfs_config(id).await?;
+    let config = if hc.hdfs_config.trim().is_empty() {
+        HashMap::new()
+    } else {
+        serde_json::from_str::<HashMap<String, String>>(&hc.hdfs_config)
+            .map_err(|e| format!("Invalid config json: {}", e))?
+    };
+    let client = JmxClient {
+        http: reqwest::Client::builder()
+            .timeout(JMX_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?,
+        spnego: config.contains_key("dfs.namenode.kerberos.principal"),
+        ccache: crate::kerberos::ccache::connection_ccache(id),
+    };
+
+    let namenodes =
+        futures::future::join_all(namenode_addresses(&hc.hdfs_url, &config).iter().map(|nn| {
+            let http_address = namenode_http_address(nn, &config);
+            query_namenode_state(&client, nn, http_address)
+        }))
+        .await;
+
+    let mut result = ClusterStatus {
+        namenodes,
+        ..Default::default()
+    };
+    //非HA的namenode也报告为active
+    let active = result.namenodes.iter_mut().find(|nn| nn.state == "active");
+    let Some(active) = active else {
+        result.auth_failed = result
+            .namenodes
+            .iter()
+            .any(|nn| nn.error.starts_with(JMX_AUTH_FAILED));
+        return Ok(result);
+    };
+    match query_fs_stats(&client, &active.http_address).await {
+        Ok((stats, safemode_message)) => {
+            result.fs_stats = Some(stats);
+            result.safemode = !safemode_message.is_empty();
+            result.safemode_message = safemode_message;
+        }
+        Err(e) => active.error = e,
+    }
+    Ok(result)
+}
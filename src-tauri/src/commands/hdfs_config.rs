@@ -1,174 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use super::hdfs_config_validate::{HdfsConfigValidation, validate_hdfs_config};
+use super::hdfs_config_xml::KRB5_CONF_KEY;
+use super::hdfs_connection::invalidate_client;
 use crate::db::db_init::DB_POOL;
-use ascii::AsciiString;
-use awol2005ex_kerbeiros::{TgtRequester, Credential};
-use awol2005ex_kerberos_keytab::Keytab;
-use awol2005ex_kerberos_crypto::Key;
-use std::net::{IpAddr, ToSocketAddrs};
-use std::fs;
+use crate::db::secret;
+use crate::kerberos::ccache;
+use crate::kerberos::kinit::{kinit_with_keytab, kinit_with_password, split_principal};
+use crate::kerberos::krb5_conf::Krb5Conf;
 use std::path::Path;
-use std::env;
-// Helper functions for Kerberos authentication using awol2005ex_kerbeiros
-
-/// Read KDC IP from Kerberos configuration file (krb5.conf)
-fn get_kdc_ip_from_config(realm: &str) -> Result<String, Box<dyn std::error::Error>> {
-    // Get KRB5_CONFIG environment variable, or use default locations
-    let config_paths = if let Ok(krb5_config) = env::var("KRB5_CONFIG") {
-        vec![krb5_config]
-    } else {
-        // Default locations for krb5.conf
-        vec![
-            "/etc/krb5.conf".to_string(),
-            "/etc/krb5/krb5.conf".to_string(),
-            "C:\\Windows\\krb5.ini".to_string(),
-            format!("{}\\krb5.ini", env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string())),
-        ]
-    };
-    
-    for config_path in config_paths {
-        if let Ok(config_content) = fs::read_to_string(&config_path) {
-            if let Some(kdc_ip) = parse_krb5_conf_for_kdc(&config_content, realm) {
-                return Ok(kdc_ip);
-            }
-        }
-    }
-    
-    Err(format!("Could not find KDC for realm {} in any krb5.conf file", realm).into())
-}
-
-/// Parse krb5.conf content to find KDC for the specified realm
-fn parse_krb5_conf_for_kdc(config_content: &str, realm: &str) -> Option<String> {
-    let mut in_realms_section = false;
-    let mut current_realm = String::new();
-    
-    for line in config_content.lines() {
-        let line = line.trim();
-        
-        if line.starts_with('[') && line.ends_with(']') {
-            let section = line[1..line.len()-1].trim();
-            in_realms_section = section == "realms" || section.ends_with("realms");
-            continue;
-        }
-        
-        if !in_realms_section {
-            continue;
-        }
-        
-        if line.starts_with(&format!("{} =", realm)) || line.starts_with(&format!("{}=", realm)) {
-            current_realm = realm.to_string();
-            continue;
-        }
-        
-        if !current_realm.is_empty() {
-            if line.contains('{') {
-                continue;
-            }
-            if line.contains('}') {
-                current_realm.clear();
-                continue;
-            }
-            
-            // Look for kdc = <hostname_or_ip>
-            if line.starts_with("kdc") || line.contains("kdc =") {
-                if let Some(kdc_part) = line.split('=').nth(1) {
-                    let kdc = kdc_part.trim().trim_matches('"').trim_matches('\'');
-                    if !kdc.is_empty() {
-                        return Some(kdc.to_string());
-                    }
-                }
-            }
-        }
-    }
-    
-    None
-}
 
-/// Load a keytab file and find the entry for the specified principal
-fn load_key_from_keytab(keytab_path: &str, principal: &str) -> Result<Key, Box<dyn std::error::Error>> {
-    let keytab_data = fs::read(keytab_path)?;
-    let keytab = Keytab::parse(&keytab_data)
-        .map_err(|e| format!("Failed to parse keytab: {:?}", e))?
-        .1;
-
-    // Find the entry matching the principal
-    for entry in keytab.entries {
-        let realm_str: String = entry.realm.try_into()?;
-        let components: Vec<String> = entry.components
-            .into_iter()
-            .map(|c| String::from_utf8(c.data))
-            .collect::<Result<Vec<_>, _>>()?;
-        
-        let principal_str = format!("{}@{}", components.join("/"), realm_str);
-        
-        if principal_str == principal {
-            // Convert keyvalue to hex string
-            let key_hex = hex::encode(&entry.key.keyvalue);
-            
-            // Create key based on keytype
-            match entry.key.keytype {
-                23 => return Ok(Key::from_rc4_key_string(&key_hex)?), // RC4-HMAC
-                17 => return Ok(Key::from_aes_128_key_string(&key_hex)?), // AES128
-                18 => return Ok(Key::from_aes_256_key_string(&key_hex)?), // AES256
-                _ => return Err(format!("Unsupported key type: {}", entry.key.keytype).into()),
-            }
-        }
-    }
-    
-    Err(format!("Principal {} not found in keytab", principal).into())
-}
-
-/// Perform kinit using keytab file
-fn kinit_with_keytab(keytab_path: &str, principal: &str, kdc_ip: &str) -> Result<Credential, Box<dyn std::error::Error>> {
-    log::info!("Performing kinit with keytab...");
-    log::info!("Keytab: {}", keytab_path);
-    log::info!("Principal: {}", principal);
-    log::info!("KDC IP: {}", kdc_ip);
-    
-    // Parse the principal to extract realm
-    let principal_parts: Vec<&str> = principal.split('@').collect();
-    if principal_parts.len() != 2 {
-        return Err("Principal must be in format username@REALM".into());
-    }
-    let realm = principal_parts[1];
-    
-    // Load the key from keytab
-    let user_key = load_key_from_keytab(keytab_path, principal)?;
-    
-    // Parse KDC address - can be IP address or hostname
-    let kdc_address: IpAddr = match kdc_ip.parse() {
-        Ok(ip) => ip,
-        Err(_) => {
-            // Try to resolve hostname to IP address
-            let kdc_socket = format!("{}:88", kdc_ip);
-            match kdc_socket.to_socket_addrs() {
-                Ok(mut addrs) => {
-                    match addrs.next() {
-                        Some(addr) => addr.ip(),
-                        None => return Err(format!("Could not resolve KDC hostname: {}", kdc_ip).into()),
-                    }
-                }
-                Err(e) => return Err(format!("Could not resolve KDC hostname '{}': {}", kdc_ip, e).into()),
-            }
-        }
-    };
-    
-    // Prepare the arguments
-    let realm_ascii = AsciiString::from_ascii(realm)
-        .map_err(|e| format!("Invalid realm: {}", e))?;
-    let username_ascii = AsciiString::from_ascii(principal_parts[0])
-        .map_err(|e| format!("Invalid username: {}", e))?;
-    
-    // Create TGT requester
-    let tgt_requester = TgtRequester::new(realm_ascii, kdc_address);
-    
-    // Request the TGT
-    let credential = tgt_requester.request(&username_ascii, Some(&user_key))
-        .map_err(|e| format!("TGT request failed: {}", e))?;
-    
-    log::info!("Successfully obtained TGT for {}", principal);
-    Ok(credential)
-}
+//kerberos密码保存在配置中的key,属于敏感配置会加密保存
+pub const KERBEROS_PASSWORD_KEY: &str = "hdfs-gui.kerberos.password";
+//没有keytab也没有密码时返回的错误,前端据此提示输入密码
+pub const KERBEROS_PASSWORD_REQUIRED: &str = "Kerberos password required";
 
 //hdfs配置
 #[derive(Debug, Default, Deserialize, Serialize, sqlx::FromRow, Clone)]
@@ -178,10 +23,35 @@ pub struct HdfsConfig {
     pub hdfs_config: String, //json其他配置
     pub hdfs_url: String,
     pub del_flag: i64, //0正常 1删除
+    #[serde(default)]
+    pub proxy_user: String, //代理用户(doAs),为空时不代理
 }
-//获取hdfs配置列表
+//获取hdfs配置列表(敏感值已掩码,不需要解密)
 #[tauri::command]
 pub async fn get_hdfs_config_list() -> Result<Vec<HdfsConfig>, String> {
+    let hdfs_config_list = query_hdfs_config_list().await?;
+    Ok(hdfs_config_list
+        .into_iter()
+        .map(|hc| HdfsConfig {
+            hdfs_config: secret::mask_config_json(&hc.hdfs_config),
+            ..hc
+        })
+        .collect())
+}
+
+//获取全部hdfs配置(敏感值已解密)
+pub async fn get_all_hdfs_configs() -> Result<Vec<HdfsConfig>, String> {
+    let mut result = vec![];
+    for hc in query_hdfs_config_list().await? {
+        result.push(HdfsConfig {
+            hdfs_config: secret::decrypt_config(&hc.hdfs_config).await?,
+            ..hc
+        });
+    }
+    Ok(result)
+}
+
+async fn query_hdfs_config_list() -> Result<Vec<HdfsConfig>, String> {
     crate::db::db_init::init_db()
         .await
         .map_err(|e| e.to_string())?;
@@ -197,34 +67,93 @@ pub async fn get_hdfs_config_list() -> Result<Vec<HdfsConfig>, String> {
     Ok(vec![])
 }
 
-//保存hdfs配置
+//保存hdfs配置,先校验,有错误时不保存并返回校验结果
 #[tauri::command]
-pub async fn save_hdfs_config(hdfs_config: HdfsConfig) -> Result<(), String> {
+pub async fn save_hdfs_config(hdfs_config: HdfsConfig) -> Result<HdfsConfigValidation, String> {
     crate::db::db_init::init_db()
         .await
         .map_err(|e| e.to_string())?;
 
-    if let Some(pool) = DB_POOL.get() {
+    let validation = check_hdfs_config(&hdfs_config).await?;
+    if validation.is_valid() {
+        store_hdfs_config(hdfs_config).await?;
+    }
+    Ok(validation)
+}
+
+//保存前的校验,掩码值按原值校验
+pub async fn check_hdfs_config(hdfs_config: &HdfsConfig) -> Result<HdfsConfigValidation, String> {
+    let plain_config = unmask_hdfs_config(hdfs_config).await?;
+    let mut validation = validate_hdfs_config(&hdfs_config.hdfs_url, &plain_config);
+    if let Err(e) = check_duplicate_name(&hdfs_config.name, hdfs_config.id).await {
+        validation.error("name", e);
+    }
+    Ok(validation)
+}
+
+//掩码值还原为原值
+async fn unmask_hdfs_config(hdfs_config: &HdfsConfig) -> Result<String, String> {
+    if hdfs_config.id > 0 {
+        let old_hdfs_config = get_one_hdfs_config(hdfs_config.id).await?;
+        secret::unmask_config_json(&hdfs_config.hdfs_config, &old_hdfs_config.hdfs_config)
+    } else {
+        Ok(hdfs_config.hdfs_config.clone())
+    }
+}
+
+//写入hdfs配置,不做校验
+pub async fn store_hdfs_config(hdfs_config: HdfsConfig) -> Result<(), String> {
+    store_hdfs_configs(vec![hdfs_config]).await
+}
+
+//在一个事务中写入多个hdfs配置,不做校验,任何一个失败时都不写入
+pub async fn store_hdfs_configs(hdfs_config_list: Vec<HdfsConfig>) -> Result<(), String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(pool) = DB_POOL.get() else {
+        return Err("Database connection pool is not initialized".to_owned());
+    };
+
+    //掩码值保留原值,敏感值加密保存
+    let mut encrypted_list = vec![];
+    for hdfs_config in hdfs_config_list {
+        check_duplicate_name(&hdfs_config.name, hdfs_config.id).await?;
+        let plain_config = unmask_hdfs_config(&hdfs_config).await?;
+        encrypted_list.push(HdfsConfig {
+            hdfs_config: secret::encrypt_config(&plain_config).await?,
+            ..hdfs_config
+        });
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for hdfs_config in &encrypted_list {
         if hdfs_config.id > 0 {
             sqlx::query(
-                "update hdfs_config set name = ?, hdfs_config = ?, hdfs_url = ? where id = ?",
+                "update hdfs_config set name = ?, hdfs_config = ?, hdfs_url = ?, proxy_user = ? where id = ?",
             )
-            .bind(hdfs_config.name)
-            .bind(hdfs_config.hdfs_config)
-            .bind(hdfs_config.hdfs_url)
+            .bind(&hdfs_config.name)
+            .bind(&hdfs_config.hdfs_config)
+            .bind(&hdfs_config.hdfs_url)
+            .bind(&hdfs_config.proxy_user)
             .bind(hdfs_config.id)
-            .execute(pool)
+            .execute(&mut *tx)
             .await
             .map_err(|e| e.to_string())?;
         } else {
-            sqlx::query("insert into hdfs_config (name, hdfs_config, hdfs_url,del_flag) values (?, ?, ? ,0)")
-        .bind(hdfs_config.name)
-        .bind(hdfs_config.hdfs_config)
-        .bind(hdfs_config.hdfs_url)
-        .execute(pool).await.map_err(|e| e.to_string())?;
+            sqlx::query("insert into hdfs_config (name, hdfs_config, hdfs_url,del_flag, proxy_user) values (?, ?, ? ,0, ?)")
+        .bind(&hdfs_config.name)
+        .bind(&hdfs_config.hdfs_config)
+        .bind(&hdfs_config.hdfs_url)
+        .bind(&hdfs_config.proxy_user)
+        .execute(&mut *tx).await.map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    for hdfs_config in &encrypted_list {
+        if hdfs_config.id > 0 {
+            invalidate_client(hdfs_config.id);
         }
-    } else {
-        return Err("Database connection pool is not initialized".to_owned());
     }
     Ok(())
 }
@@ -236,6 +165,8 @@ pub async fn delete_hdfs_config(id: i64) -> Result<(), String> {
         .map_err(|e| e.to_string())?;
 
     if let Some(pool) = DB_POOL.get() {
+        invalidate_client(id);
+        ccache::remove_ccache(id);
         sqlx::query("update hdfs_config set del_flag=1 where id = ?")
             .bind(id)
             .execute(pool)
@@ -247,89 +178,200 @@ pub async fn delete_hdfs_config(id: i64) -> Result<(), String> {
     Ok(())
 }
 
-//初始化kerberos
+//已删除的hdfs配置列表(敏感值已掩码)
 #[tauri::command]
-pub async fn init_connection(id: i64) -> Result<(), String> {
+pub async fn get_deleted_hdfs_config_list() -> Result<Vec<HdfsConfig>, String> {
     crate::db::db_init::init_db()
         .await
         .map_err(|e| e.to_string())?;
 
-    let hc = get_one_hdfs_config(id).await.map_err(|e| e.to_string())?;
-    log::info!(" Hdfs Config: {:?}", &hc);
-    if let Ok(config_json) = serde_json::from_str::<serde_json::Value>(&hc.hdfs_config) {
-        if let Some(principal) = config_json.get("dfs.namenode.kerberos.principal") {
-            if let Some(keytab) = config_json.get("dfs.namenode.keytab.file") {
-                let principal_s = principal.as_str().unwrap_or_default();
-                let keytab_s = keytab.as_str().unwrap_or_default();
-                
-                // Check if keytab file exists
-                if !Path::new(&keytab_s).exists() {
-                    return Err(format!("Keytab file not found: {}", keytab_s));
-                }
-                
-                // Parse the principal to extract realm for KDC lookup
-                let principal_parts: Vec<&str> = principal_s.split('@').collect();
-                if principal_parts.len() != 2 {
-                    return Err("Principal must be in format username@REALM".to_owned());
-                }
-                let realm = principal_parts[1];
-                
-                // Get KDC IP from Kerberos configuration file
-                let kdc_ip = match get_kdc_ip_from_config(realm) {
-                    Ok(ip) => ip,
-                    Err(e) => {
-                        log::warn!("Could not get KDC IP from config: {}", e);
-                        log::warn!("Falling back to default KDC IP: 192.168.1.100");
-                        "192.168.1.100".to_string()
-                    }
-                };
-                
-                // Perform kinit using awol2005ex_kerbeiros library
-                match kinit_with_keytab(&keytab_s, &principal_s, &kdc_ip) {
-                    Ok(credential) => {
-                        log::info!("Successfully obtained TGT for {}", principal_s);
-                        
-                        // Set up credential cache path
-                        let ccache_file = if cfg!(target_os = "windows") {
-                            let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
-                            format!("{}/hdfs_gui_ccache", current_dir.to_str().unwrap_or_default())
-                        } else {
-                            "/tmp/hdfs_gui_ccache".to_string()
-                        };
-                        
-                        // Save the credential to the cache file
-                        credential.save_into_ccache_file(&ccache_file)
-                            .map_err(|e| format!("Failed to save credential cache: {}", e))?;
-                        
-                        // Set KRB5CCNAME environment variable
-                        unsafe {
-                            std::env::set_var("KRB5CCNAME", ccache_file.clone());
-                        }
-                        
-                        log::info!("Saved credential cache to: {}", ccache_file);
-                        Ok(())
-                    }
-                    Err(e) => {
-                        log::error!("Kinit failed: {}", e);
-                        Err(format!("Kerberos authentication failed: {}", e))
-                    }
-                }
-            } else {
-                log::warn!("No keytab file found in configuration");
-                Ok(())
+    if let Some(pool) = DB_POOL.get() {
+        let hdfs_config_list: Vec<HdfsConfig> =
+            sqlx::query_as::<_, HdfsConfig>("select * from hdfs_config where del_flag = 1")
+                .fetch_all(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        return Ok(hdfs_config_list
+            .into_iter()
+            .map(|hc| HdfsConfig {
+                hdfs_config: secret::mask_config_json(&hc.hdfs_config),
+                ..hc
+            })
+            .collect());
+    }
+    Err("Database connection pool is not initialized".to_owned())
+}
+
+//恢复已删除的hdfs配置,已有同名配置时不能恢复
+#[tauri::command]
+pub async fn restore_hdfs_config(id: i64) -> Result<(), String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(pool) = DB_POOL.get() {
+        let hc = query_one_hdfs_config(id).await?;
+        check_duplicate_name(&hc.name, hc.id).await?;
+        sqlx::query("update hdfs_config set del_flag=0 where id = ?")
+            .bind(id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    } else {
+        return Err("Database connection pool is not initialized".to_owned());
+    }
+    Ok(())
+}
+
+//彻底删除已删除的hdfs配置,id为空时清空全部,返回删除的数量
+#[tauri::command]
+pub async fn purge_hdfs_config(id: Option<i64>) -> Result<u64, String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(pool) = DB_POOL.get() {
+        let result = match id {
+            Some(id) => {
+                sqlx::query("delete from hdfs_config where id = ? and del_flag = 1")
+                    .bind(id)
+                    .execute(pool)
+                    .await
+            }
+            None => {
+                sqlx::query("delete from hdfs_config where del_flag = 1")
+                    .execute(pool)
+                    .await
             }
-        } else {
-            log::warn!("No Kerberos principal found in configuration");
-            Ok(())
         }
-    } else {
+        .map_err(|e| e.to_string())?;
+        log::info!("Purged {} deleted hdfs configs", result.rows_affected());
+        return Ok(result.rows_affected());
+    }
+    Err("Database connection pool is not initialized".to_owned())
+}
+
+//未删除的配置中不能有重名
+async fn check_duplicate_name(name: &str, id: i64) -> Result<(), String> {
+    if let Some(pool) = DB_POOL.get() {
+        let count: i64 = sqlx::query_scalar(
+            "select count(*) from hdfs_config where name = ? and del_flag = 0 and id != ?",
+        )
+        .bind(name)
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if count > 0 {
+            return Err(format!("A connection named {} already exists", name));
+        }
+    }
+    Ok(())
+}
+
+//初始化kerberos,没有keytab时使用密码登录
+//password为本次输入的密码,save_password为true时加密保存到配置,为false时删除已保存的密码
+#[tauri::command]
+pub async fn init_connection(
+    id: i64,
+    password: Option<String>,
+    save_password: Option<bool>,
+) -> Result<(), String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let hc = get_one_hdfs_config(id).await.map_err(|e| e.to_string())?;
+    log::info!(
+        " Hdfs Config: {} {} {}",
+        &hc.name,
+        &hc.hdfs_url,
+        secret::mask_config_json(&hc.hdfs_config)
+    );
+    let Ok(mut config_json) =
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&hc.hdfs_config)
+    else {
         log::warn!("Failed to parse HDFS configuration JSON");
-        Ok(())
+        return Ok(());
+    };
+    let Some(principal) = config_json.get("dfs.namenode.kerberos.principal") else {
+        log::warn!("No Kerberos principal found in configuration");
+        return Ok(());
+    };
+    let principal_s = principal.as_str().unwrap_or_default().to_string();
+
+    // Load krb5.conf, the realm's KDCs are tried in order
+    let krb5_conf = Krb5Conf::load(config_json.get(KRB5_CONF_KEY).and_then(|v| v.as_str()))?;
+    let (principal_name, realm) = split_principal(&principal_s, &krb5_conf)?;
+    let principal_s = format!("{}@{}", principal_name, realm);
+
+    // Perform kinit using awol2005ex_kerbeiros library
+    let input_password = password.filter(|p| !p.is_empty());
+    let credential = if let Some(keytab) = config_json.get("dfs.namenode.keytab.file") {
+        let keytab_s = keytab.as_str().unwrap_or_default().to_string();
+
+        // Check if keytab file exists
+        if !Path::new(&keytab_s).exists() {
+            return Err(format!("Keytab file not found: {}", keytab_s));
+        }
+        kinit_with_keytab(&krb5_conf, &keytab_s, &principal_s).await
+    } else {
+        let stored_password = config_json
+            .get(KERBEROS_PASSWORD_KEY)
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.is_empty())
+            .map(|p| p.to_string());
+        let Some(password_s) = input_password.clone().or(stored_password) else {
+            return Err(format!("{}: {}", KERBEROS_PASSWORD_REQUIRED, &principal_s));
+        };
+        kinit_with_password(&krb5_conf, &password_s, &principal_s).await
+    };
+    let credential = credential.map_err(|e| {
+        log::error!("Kinit failed: {}", e);
+        format!("Kerberos authentication failed: {}", e)
+    })?;
+    log::info!("Successfully obtained TGT for {}", principal_s);
+
+    // Save the credential to the connection's own cache file
+    let ccache_file = ccache::save_credential(id, &credential)?;
+    drop(credential);
+
+    log::info!("Saved credential cache to: {}", ccache_file.display());
+    //新票据需要新建客户端才会生效
+    invalidate_client(id);
+
+    //密码只在用户选择时加密保存
+    let changed = match (save_password, input_password) {
+        (Some(true), Some(password_s)) => {
+            config_json.insert(
+                KERBEROS_PASSWORD_KEY.to_string(),
+                serde_json::Value::String(password_s),
+            );
+            true
+        }
+        (Some(false), _) => config_json.remove(KERBEROS_PASSWORD_KEY).is_some(),
+        _ => false,
+    };
+    if changed {
+        store_hdfs_config(HdfsConfig {
+            hdfs_config: serde_json::to_string_pretty(&config_json).map_err(|e| e.to_string())?,
+            ..hc
+        })
+        .await?;
     }
+    Ok(())
 }
 
-//获取单个hdfs配置
+//获取单个hdfs配置(敏感值已解密)
 pub async fn get_one_hdfs_config(id: i64) -> Result<HdfsConfig, String> {
+    let hc = query_one_hdfs_config(id).await?;
+    Ok(HdfsConfig {
+        hdfs_config: secret::decrypt_config(&hc.hdfs_config).await?,
+        ..hc
+    })
+}
+
+async fn query_one_hdfs_config(id: i64) -> Result<HdfsConfig, String> {
     if let Some(pool) = DB_POOL.get() {
         let hdfs_config_list: Vec<HdfsConfig> =
             sqlx::query_as::<_, HdfsConfig>("select * from hdfs_config where id=?")
@@ -346,27 +388,34 @@ pub async fn get_one_hdfs_config(id: i64) -> Result<HdfsConfig, String> {
         return Err("Database connection pool is not initialized".to_owned());
     }
 }
-//获取单个hdfs配置
+//获取单个hdfs配置(敏感值已掩码)
 #[tauri::command]
 pub async fn get_hdfs_config(id: i64) -> Result<HdfsConfig, String> {
     crate::db::db_init::init_db()
         .await
         .map_err(|e| e.to_string())?;
 
-    return get_one_hdfs_config(id).await.map_err(|e| e.to_string());
+    let hc = query_one_hdfs_config(id).await?;
+    return Ok(HdfsConfig {
+        hdfs_config: secret::mask_config_json(&hc.hdfs_config),
+        ..hc
+    });
 }
-//获取当前用户名
+//获取当前用户名,配置了代理用户时为代理用户
 pub async fn get_hdfs_username(id: i64) -> Result<String, String> {
     let hdfs_config = get_one_hdfs_config(id).await.map_err(|e| e.to_string());
 
     if let Ok(hc) = hdfs_config {
+        if !hc.proxy_user.is_empty() {
+            return Ok(hc.proxy_user);
+        }
         if let Ok(config_json) = serde_json::from_str::<serde_json::Value>(&hc.hdfs_config) {
             if let Some(username) = config_json.get("dfs.namenode.kerberos.principal") {
                 return Ok(username
                     .as_str()
                     .unwrap_or_default()
                     .to_string()
-                    .split("@")
+                    .split(['@', '/'])
                     .next()
                     .unwrap_or_default()
                     .to_owned());
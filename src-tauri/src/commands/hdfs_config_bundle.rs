@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::hdfs_config::{HdfsConfig, check_hdfs_config, get_all_hdfs_configs, store_hdfs_configs};
+use super::hdfs_config_xml::KRB5_CONF_KEY;
+use crate::db::secret::{MASKED_VALUE, is_sensitive_key};
+
+//当前导出格式版本
+pub const BUNDLE_VERSION: u32 = 1;
+
+//只在本机有效的路径配置,导入时可选择去掉
+pub const LOCAL_PATH_KEYS: [&str; 2] = ["dfs.namenode.keytab.file", KRB5_CONF_KEY];
+
+//连接配置导出包
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct HdfsConfigBundle {
+    pub version: u32,
+    pub exported_at: String,
+    pub connections: Vec<HdfsConfigBundleEntry>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct HdfsConfigBundleEntry {
+    pub name: String,
+    pub hdfs_url: String,
+    #[serde(default)]
+    pub proxy_user: String,
+    #[serde(deserialize_with = "deserialize_config_values")]
+    pub hdfs_config: BTreeMap<String, String>,
+}
+
+//配置值都按字符串保存,导入包中的数字和布尔值转为字符串
+fn deserialize_config_values<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error> {
+    let values = BTreeMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    values
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(v) => Ok((key, v)),
+            serde_json::Value::Number(v) => Ok((key, v.to_string())),
+            serde_json::Value::Bool(v) => Ok((key, v.to_string())),
+            _ => Err(serde::de::Error::custom(format!(
+                "value of {} is not a string, number or boolean",
+                key
+            ))),
+        })
+        .collect()
+}
+
+//导入时重名处理方式
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleConflictPolicy {
+    //合并到已有同名配置,导入的key覆盖已有key
+    Merge,
+    //重命名后新增
+    Rename,
+    //跳过
+    Skip,
+}
+
+//单个连接的导入结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct HdfsConfigBundleImportItem {
+    pub name: String,
+    //created / merged / renamed / skipped
+    pub action: String,
+    //实际保存的名称
+    pub saved_name: String,
+    //被去掉的本机路径key,以及新建连接时导出包中被掩码的敏感key
+    pub removed_keys: Vec<String>,
+    //合并时被导入包替换掉的原hdfs_url,相同时为空
+    pub replaced_hdfs_url: Option<String>,
+}
+
+fn is_toml_path(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false)
+}
+
+fn parse_config_map(hdfs_config: &str) -> Result<BTreeMap<String, String>, String> {
+    if hdfs_config.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    serde_json::from_str::<BTreeMap<String, String>>(hdfs_config).map_err(|e| e.to_string())
+}
+
+fn next_free_name(name: &str, used_names: &[String]) -> String {
+    let mut n = 2;
+    loop {
+        let candidate = format!("{} ({})", name, n);
+        if !used_names.iter().any(|used| used == &candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+//导出hdfs配置到文件,扩展名为.toml时导出toml,否则导出json
+//include_secrets为false时敏感值导出为掩码,导入合并时保留已有值
+#[tauri::command]
+pub async fn export_hdfs_config_bundle(
+    ids: Vec<i64>,
+    file_path: String,
+    include_secrets: bool,
+) -> Result<usize, String> {
+    let hdfs_config_list = get_all_hdfs_configs().await?;
+
+    let mut connections = vec![];
+    for hc in hdfs_config_list
+        .into_iter()
+        .filter(|hc| ids.contains(&hc.id))
+    {
+        let mut hdfs_config = parse_config_map(&hc.hdfs_config)
+            .map_err(|e| format!("Invalid config json of {}: {}", &hc.name, e))?;
+        if !include_secrets {
+            for (key, value) in hdfs_config.iter_mut() {
+                if is_sensitive_key(key) && !value.is_empty() {
+                    *value = MASKED_VALUE.to_string();
+                }
+            }
+        }
+        connections.push(HdfsConfigBundleEntry {
+            hdfs_config,
+            name: hc.name,
+            hdfs_url: hc.hdfs_url,
+            proxy_user: hc.proxy_user,
+        });
+    }
+    let bundle = HdfsConfigBundle {
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        connections,
+    };
+
+    let content = if is_toml_path(&file_path) {
+        toml::to_string_pretty(&bundle).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?
+    };
+    std::fs::write(&file_path, content).map_err(|e| e.to_string())?;
+
+    Ok(bundle.connections.len())
+}
+
+//从文件导入hdfs配置,先校验全部连接,有错误时一个都不导入
+#[tauri::command]
+pub async fn import_hdfs_config_bundle(
+    file_path: String,
+    conflict_policy: BundleConflictPolicy,
+    exclude_local_paths: bool,
+) -> Result<Vec<HdfsConfigBundleImportItem>, String> {
+    let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+    let bundle: HdfsConfigBundle = if is_toml_path(&file_path) {
+        toml::from_str(&content).map_err(|e| e.to_string())?
+    } else {
+        serde_json::from_str(&content).map_err(|e| e.to_string())?
+    };
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!(
+            "Unsupported bundle version {}, the newest supported version is {}",
+            bundle.version, BUNDLE_VERSION
+        ));
+    }
+
+    let existing_list = get_all_hdfs_configs().await?;
+    let mut used_names: Vec<String> = existing_list.iter().map(|hc| hc.name.clone()).collect();
+
+    let mut result = vec![];
+    let mut hdfs_config_list = vec![];
+    let mut errors = vec![];
+    for mut entry in bundle.connections {
+        let mut removed_keys = vec![];
+        if exclude_local_paths {
+            for key in LOCAL_PATH_KEYS {
+                if entry.hdfs_config.remove(key).is_some() {
+                    removed_keys.push(key.to_string());
+                }
+            }
+        }
+
+        let existing = existing_list.iter().find(|hc| hc.name == entry.name);
+        let (action, hdfs_config) = match (existing, conflict_policy) {
+            //同一导入包中的重名配置
+            (None, _) if used_names.contains(&entry.name) => (
+                "renamed",
+                HdfsConfig {
+                    name: next_free_name(&entry.name, &used_names),
+                    ..Default::default()
+                },
+            ),
+            (None, _) => (
+                "created",
+                HdfsConfig {
+                    name: entry.name.clone(),
+                    ..Default::default()
+                },
+            ),
+            (Some(_), BundleConflictPolicy::Skip) => {
+                result.push(HdfsConfigBundleImportItem {
+                    name: entry.name.clone(),
+                    action: "skipped".to_owned(),
+                    saved_name: entry.name,
+                    removed_keys,
+                    replaced_hdfs_url: None,
+                });
+                continue;
+            }
+            (Some(existing), BundleConflictPolicy::Merge) => {
+                //已有配置无法解析时报错,不能用导入的key覆盖掉全部已有key
+                let mut merged = parse_config_map(&existing.hdfs_config)
+                    .map_err(|e| format!("Invalid config json of {}: {}", &existing.name, e))?;
+                merged.append(&mut entry.hdfs_config);
+                entry.hdfs_config = merged;
+                ("merged", existing.clone())
+            }
+            (Some(_), BundleConflictPolicy::Rename) => (
+                "renamed",
+                HdfsConfig {
+                    name: next_free_name(&entry.name, &used_names),
+                    ..Default::default()
+                },
+            ),
+        };
+
+        //新建的连接没有原值可保留,去掉掩码值
+        if action != "merged" {
+            entry.hdfs_config.retain(|key, value| {
+                let masked = value == MASKED_VALUE;
+                if masked {
+                    removed_keys.push(key.clone());
+                }
+                !masked
+            });
+        }
+
+        let saved_name = hdfs_config.name.clone();
+        let replaced_hdfs_url = Some(hdfs_config.hdfs_url.clone())
+            .filter(|url| action == "merged" && url != &entry.hdfs_url);
+        used_names.push(saved_name.clone());
+        //合并时导入包中没有代理用户则保留原值
+        let proxy_user = if entry.proxy_user.is_empty() {
+            hdfs_config.proxy_user.clone()
+        } else {
+            entry.proxy_user
+        };
+        let hdfs_config = HdfsConfig {
+            hdfs_url: entry.hdfs_url,
+            proxy_user,
+            hdfs_config: serde_json::to_string_pretty(&entry.hdfs_config)
+                .map_err(|e| e.to_string())?,
+            ..hdfs_config
+        };
+        let validation = check_hdfs_config(&hdfs_config).await?;
+        for (key, messages) in validation.errors {
+            errors.push(format!("{}: {}: {}", &entry.name, key, messages.join("; ")));
+        }
+        hdfs_config_list.push(hdfs_config);
+
+        result.push(HdfsConfigBundleImportItem {
+            name: entry.name,
+            action: action.to_owned(),
+            saved_name,
+            removed_keys,
+            replaced_hdfs_url,
+        });
+    }
+    if !errors.is_empty() {
+        return Err(format!(
+            "Nothing imported, invalid connections: {}",
+            errors.join(", ")
+        ));
+    }
+    store_hdfs_configs(hdfs_config_list).await?;
+    Ok(result)
+}
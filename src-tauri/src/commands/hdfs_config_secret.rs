@@ -0,0 +1,31 @@
+use crate::db::secret::{self, SecretStoreStatus};
+
+//获取配置加密状态
+#[tauri::command]
+pub async fn get_secret_store_status() -> Result<SecretStoreStatus, String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    secret::status().await
+}
+
+//输入主口令解锁加密配置
+#[tauri::command]
+pub async fn unlock_secret_store(passphrase: String) -> Result<(), String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    secret::unlock(&passphrase).await
+}
+
+//更换主密钥,传入口令时改为口令模式,否则生成新的密钥文件
+#[tauri::command]
+pub async fn rotate_secret_key(passphrase: Option<String>) -> Result<(), String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    secret::rotate(passphrase).await
+}
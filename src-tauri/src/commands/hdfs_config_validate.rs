@@ -0,0 +1,432 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::hdfs_config_xml::KRB5_CONF_KEY;
+use super::hdfs_connection::{hdfs_url_authority, is_webhdfs_url};
+use crate::backend::viewfs::{is_viewfs_url, mount_table, split_target};
+use crate::kerberos::keytab;
+use crate::kerberos::kinit::split_principal;
+use crate::kerberos::krb5_conf::{DEFAULT_KDC_PORT, Krb5Conf};
+
+//校验结果中hdfs_url和整个json的key
+pub const HDFS_URL_ENTRY: &str = "hdfs_url";
+pub const HDFS_CONFIG_ENTRY: &str = "hdfs_config";
+
+const PRINCIPAL_KEY: &str = "dfs.namenode.kerberos.principal";
+const KEYTAB_KEY: &str = "dfs.namenode.keytab.file";
+
+//取值为true/false的配置
+static BOOLEAN_KEYS: &[&str] = &[
+    "dfs.client.use.datanode.hostname",
+    "dfs.client.read.shortcircuit",
+    "dfs.permissions.enabled",
+    "dfs.encrypt.data.transfer",
+    "hadoop.security.authorization",
+];
+
+//数字配置的取值形式
+#[derive(Clone, Copy)]
+enum NumberKind {
+    Plain,
+    //可以带k/m/g/t/p/e后缀
+    Size,
+    //可以带ms/s/m/h/d后缀
+    Duration,
+}
+
+static NUMBER_KEYS: &[(&str, NumberKind)] = &[
+    ("dfs.replication", NumberKind::Plain),
+    ("dfs.blocksize", NumberKind::Size),
+    ("dfs.bytes-per-checksum", NumberKind::Size),
+    ("dfs.client-write-packet-size", NumberKind::Size),
+    ("dfs.client.block.write.retries", NumberKind::Plain),
+    ("dfs.client.retry.max.attempts", NumberKind::Plain),
+    ("dfs.client.failover.max.attempts", NumberKind::Plain),
+    ("dfs.client.socket-timeout", NumberKind::Duration),
+    ("ipc.client.connect.timeout", NumberKind::Duration),
+    ("ipc.client.connect.max.retries", NumberKind::Plain),
+];
+
+//只能取固定值的配置,逗号分隔时逐个检查
+static ENUM_KEYS: &[(&str, &[&str])] = &[
+    ("hadoop.security.authentication", &["simple", "kerberos"]),
+    (
+        "hadoop.rpc.protection",
+        &["authentication", "integrity", "privacy"],
+    ),
+    (
+        "dfs.data.transfer.protection",
+        &["authentication", "integrity", "privacy"],
+    ),
+];
+
+//配置校验结果,按配置项分组
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct HdfsConfigValidation {
+    pub errors: BTreeMap<String, Vec<String>>,
+    pub warnings: BTreeMap<String, Vec<String>>,
+}
+
+impl HdfsConfigValidation {
+    pub fn error(&mut self, key: &str, message: String) {
+        self.errors
+            .entry(key.to_string())
+            .or_default()
+            .push(message);
+    }
+
+    pub fn warning(&mut self, key: &str, message: String) {
+        self.warnings
+            .entry(key.to_string())
+            .or_default()
+            .push(message);
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn is_boolean_key(key: &str) -> bool {
+    BOOLEAN_KEYS.contains(&key) || key.ends_with(".enabled")
+}
+
+fn parse_number(value: &str, kind: NumberKind) -> bool {
+    let value = value.trim().to_lowercase();
+    let digits = match kind {
+        NumberKind::Plain => value.as_str(),
+        NumberKind::Size => value.trim_end_matches(['k', 'm', 'g', 't', 'p', 'e']),
+        NumberKind::Duration => ["ms", "s", "m", "h", "d"]
+            .iter()
+            .find_map(|unit| value.strip_suffix(unit))
+            .unwrap_or(value.as_str()),
+    };
+    digits.parse::<f64>().is_ok()
+}
+
+//host:port形式的地址
+fn check_address(validation: &mut HdfsConfigValidation, key: &str, value: &str) {
+    match value.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+        _ => validation.error(key, format!("{} is not a host:port address", value)),
+    }
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+//nameservice和HA namenode配置
+fn check_ha(
+    validation: &mut HdfsConfigValidation,
+    hdfs_url: &str,
+    config: &HashMap<String, String>,
+) {
+    let nameservices = config
+        .get("dfs.nameservices")
+        .map(|v| split_list(v))
+        .unwrap_or_default();
+    //viewfs检查每个挂载目标的nameservice
+    let fs_urls: Vec<String> = if is_viewfs_url(hdfs_url) {
+        mount_table(hdfs_url, config)
+            .targets()
+            .into_iter()
+            .filter_map(|target| split_target(target).ok())
+            .map(|(fs_url, _)| fs_url)
+            .collect()
+    } else {
+        vec![hdfs_url.to_string()]
+    };
+    let webhdfs = fs_urls.iter().any(|fs_url| is_webhdfs_url(fs_url));
+
+    for ns in nameservices.iter() {
+        let namenodes_key = format!("dfs.ha.namenodes.{}", ns);
+        let Some(namenodes) = config.get(&namenodes_key) else {
+            if !config.contains_key(&format!("dfs.namenode.rpc-address.{}", ns)) {
+                validation.error(
+                    &namenodes_key,
+                    format!("nameservice {} has no namenodes", ns),
+                );
+            }
+            continue;
+        };
+        let namenodes = split_list(namenodes);
+        if namenodes.is_empty() {
+            validation.error(&namenodes_key, "no namenode listed".to_string());
+        }
+        for nn in namenodes.iter() {
+            let rpc_key = format!("dfs.namenode.rpc-address.{}.{}", ns, nn);
+            //webhdfs连接只需要namenode的web地址
+            let web_address = ["dfs.namenode.http-address", "dfs.namenode.https-address"]
+                .iter()
+                .map(|key| format!("{}.{}.{}", key, ns, nn))
+                .find(|key| config.contains_key(key))
+                .filter(|_| webhdfs);
+            match (config.get(&rpc_key), web_address) {
+                (Some(address), _) => check_address(validation, &rpc_key, address),
+                (None, Some(web_key)) => check_address(validation, &web_key, &config[&web_key]),
+                (None, None) => validation.error(
+                    &rpc_key,
+                    format!("namenode {} of {} has no rpc address", nn, ns),
+                ),
+            }
+        }
+    }
+
+    //未声明的nameservice多半是拼写错误
+    for key in config.keys() {
+        let ns_and_nn = if let Some(ns) = key.strip_prefix("dfs.ha.namenodes.") {
+            Some((ns, None))
+        } else {
+            key.strip_prefix("dfs.namenode.rpc-address.")
+                .map(|rest| match rest.split_once('.') {
+                    Some((ns, nn)) if nameservices.contains(&ns) => (ns, Some(nn)),
+                    _ => (rest, None),
+                })
+        };
+        let Some((ns, nn)) = ns_and_nn else {
+            continue;
+        };
+        if !nameservices.is_empty() && !nameservices.contains(&ns) && !ns.contains(':') {
+            validation.warning(key, format!("{} is not listed in dfs.nameservices", ns));
+        }
+        if let Some(nn) = nn {
+            let listed = config
+                .get(&format!("dfs.ha.namenodes.{}", ns))
+                .map(|v| split_list(v).contains(&nn))
+                .unwrap_or(false);
+            if !listed {
+                validation.warning(
+                    key,
+                    format!("namenode {} is not listed in dfs.ha.namenodes.{}", nn, ns),
+                );
+            }
+        }
+    }
+
+    //hdfs_url指向nameservice或host:port
+    for fs_url in fs_urls.iter() {
+        let authority = hdfs_url_authority(fs_url);
+        if authority.is_empty() {
+            continue;
+        }
+        let has_port = authority
+            .rsplit_once(':')
+            .map(|(_, port)| port.parse::<u16>().is_ok())
+            .unwrap_or(false);
+        if has_port || nameservices.contains(&authority.as_str()) {
+            continue;
+        }
+        if config.contains_key(&format!("dfs.ha.namenodes.{}", authority)) {
+            validation.warning(
+                "dfs.nameservices",
+                format!("{} is used by {} but not listed", authority, fs_url),
+            );
+        } else if !nameservices.is_empty() {
+            validation.error(
+                HDFS_URL_ENTRY,
+                format!(
+                    "{} is not a declared nameservice ({})",
+                    authority,
+                    nameservices.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+//viewfs挂载表:至少一个挂载点,目标为hdfs或webhdfs地址
+fn check_mount_table(
+    validation: &mut HdfsConfigValidation,
+    hdfs_url: &str,
+    config: &HashMap<String, String>,
+) {
+    let table = mount_table(hdfs_url, config);
+    if table.links.is_empty() && table.fallback.is_none() {
+        validation.error(
+            HDFS_URL_ENTRY,
+            format!("no {}link.* configured for {}", table.prefix, hdfs_url),
+        );
+    }
+    let links = table
+        .links
+        .iter()
+        .map(|link| (format!("{}link.{}", table.prefix, link.path), &link.target));
+    let fallback = table
+        .fallback
+        .iter()
+        .map(|target| (format!("{}linkFallback", table.prefix), target));
+    for (key, target) in links.chain(fallback) {
+        match split_target(target) {
+            Err(e) => validation.error(&key, e),
+            Ok((fs_url, _)) if !fs_url.starts_with("hdfs://") && !is_webhdfs_url(&fs_url) => {
+                validation.error(
+                    &key,
+                    format!(
+                        "{} is not an hdfs://, webhdfs:// or swebhdfs:// target",
+                        target
+                    ),
+                )
+            }
+            Ok(_) => {}
+        }
+    }
+    for key in table.unsupported.iter() {
+        validation.warning(key, "only link and linkFallback are supported".to_string());
+    }
+}
+
+//kerberos配置:keytab存在且包含principal
+fn check_security(validation: &mut HdfsConfigValidation, config: &HashMap<String, String>) {
+    let kerberos = config
+        .get("hadoop.security.authentication")
+        .map(|v| v.trim().eq_ignore_ascii_case("kerberos"))
+        .unwrap_or(false);
+    let principal = config.get(PRINCIPAL_KEY).filter(|p| !p.trim().is_empty());
+
+    if kerberos && principal.is_none() {
+        validation.error(
+            PRINCIPAL_KEY,
+            "required when hadoop.security.authentication is kerberos".to_string(),
+        );
+    }
+    if principal.is_some_and(|p| !p.contains('@')) {
+        validation.warning(
+            PRINCIPAL_KEY,
+            "no realm, the default_realm of krb5.conf is used".to_string(),
+        );
+    }
+
+    if let Some(keytab_path) = config.get(KEYTAB_KEY).filter(|p| !p.trim().is_empty()) {
+        if !Path::new(keytab_path).is_file() {
+            validation.error(KEYTAB_KEY, format!("{} does not exist", keytab_path));
+        } else {
+            match keytab::list_keytab(keytab_path) {
+                Err(e) => validation.error(KEYTAB_KEY, e),
+                Ok(entries) => match principal {
+                    Some(principal) if principal.contains('@') => {
+                        if keytab::pick_entry(&entries, principal).is_none() {
+                            let message = if entries.iter().any(|e| &e.principal == principal) {
+                                format!("no supported enctype for {}", principal)
+                            } else {
+                                format!("{} is not in the keytab", principal)
+                            };
+                            validation.error(KEYTAB_KEY, message);
+                        }
+                    }
+                    Some(_) => {}
+                    None => validation.warning(
+                        KEYTAB_KEY,
+                        format!("keytab is not used without {}", PRINCIPAL_KEY),
+                    ),
+                },
+            }
+        }
+    }
+
+    let missing_krb5_conf = config
+        .get(KRB5_CONF_KEY)
+        .filter(|p| !p.trim().is_empty() && !Path::new(p).is_file());
+    if let Some(krb5_conf) = missing_krb5_conf {
+        validation.error(KRB5_CONF_KEY, format!("{} does not exist", krb5_conf));
+    } else if let Some(principal) = principal {
+        check_kdc_ports(validation, config, principal);
+    }
+}
+
+//kinit只能访问88端口的KDC,krb5.conf读取失败时由登录时报错
+fn check_kdc_ports(
+    validation: &mut HdfsConfigValidation,
+    config: &HashMap<String, String>,
+    principal: &str,
+) {
+    let Ok(krb5_conf) = Krb5Conf::load(config.get(KRB5_CONF_KEY).map(|p| p.as_str())) else {
+        return;
+    };
+    let Ok((_, realm)) = split_principal(principal, &krb5_conf) else {
+        return;
+    };
+    let Ok(kdcs) = krb5_conf.kdcs(&realm) else {
+        return;
+    };
+    for kdc in kdcs.iter().filter(|kdc| kdc.port != DEFAULT_KDC_PORT) {
+        validation.error(
+            KRB5_CONF_KEY,
+            format!(
+                "KDC {} of realm {} is not on port {}, other ports are not supported",
+                kdc, realm, DEFAULT_KDC_PORT
+            ),
+        );
+    }
+}
+
+//布尔、数字和枚举值
+fn check_values(validation: &mut HdfsConfigValidation, config: &HashMap<String, String>) {
+    for (key, value) in config.iter() {
+        if is_boolean_key(key) && !["true", "false"].contains(&value.trim().to_lowercase().as_str())
+        {
+            validation.error(key, format!("{} is not true or false", value));
+        }
+        let number_kind = NUMBER_KEYS.iter().find(|(k, _)| k == key);
+        if number_kind.is_some_and(|(_, kind)| !parse_number(value, *kind)) {
+            validation.error(key, format!("{} is not a number", value));
+        }
+        if let Some((_, allowed)) = ENUM_KEYS.iter().find(|(k, _)| k == key) {
+            for v in split_list(value) {
+                if !allowed.contains(&v.to_lowercase().as_str()) {
+                    validation.error(key, format!("{} is not one of {}", v, allowed.join(", ")));
+                }
+            }
+        }
+    }
+}
+
+//校验hdfs_url和json配置,hdfs_config中的敏感值需为明文
+pub fn validate_hdfs_config(hdfs_url: &str, hdfs_config: &str) -> HdfsConfigValidation {
+    let mut validation = HdfsConfigValidation::default();
+
+    let hdfs_url = hdfs_url.trim();
+    if hdfs_url.is_empty() {
+        validation.error(HDFS_URL_ENTRY, "hdfs_url is required".to_string());
+    } else if !hdfs_url.starts_with("hdfs://")
+        && !is_webhdfs_url(hdfs_url)
+        && !is_viewfs_url(hdfs_url)
+    {
+        validation.error(
+            HDFS_URL_ENTRY,
+            format!(
+                "{} does not start with hdfs://, webhdfs://, swebhdfs:// or viewfs://",
+                hdfs_url
+            ),
+        );
+    }
+
+    let config = if hdfs_config.trim().is_empty() {
+        HashMap::new()
+    } else {
+        match serde_json::from_str::<HashMap<String, String>>(hdfs_config) {
+            Ok(config) => config,
+            Err(e) => {
+                validation.error(
+                    HDFS_CONFIG_ENTRY,
+                    format!("not a json object of string values: {}", e),
+                );
+                return validation;
+            }
+        }
+    };
+
+    if is_viewfs_url(hdfs_url) {
+        check_mount_table(&mut validation, hdfs_url, &config);
+    }
+    check_ha(&mut validation, hdfs_url, &config);
+    check_security(&mut validation, &config);
+    check_values(&mut validation, &config);
+    validation
+}
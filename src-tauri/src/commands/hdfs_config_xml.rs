@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::hdfs_config::HdfsConfig;
+
+//hdfs_url来源
+pub const DEFAULT_FS_KEY: &str = "fs.defaultFS";
+//krb5.conf路径保存到配置中的key
+pub const KRB5_CONF_KEY: &str = "java.security.krb5.conf";
+
+//导入时保留的配置前缀,其他key会被忽略
+static KEPT_KEY_PREFIXES: [&str; 4] = ["dfs.", "fs.viewfs.", "hadoop.security.", "hadoop.rpc."];
+
+//Hadoop Configuration的变量替换最大深度
+const MAX_SUBST: usize = 20;
+
+//从配置目录导入的结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct HdfsConfigImport {
+    //可直接保存的配置
+    pub hdfs_config: HdfsConfig,
+    //已读取的配置文件
+    pub files: Vec<String>,
+    //被忽略的key
+    pub ignored_keys: Vec<String>,
+    //无法解析的${var}变量
+    pub unresolved_vars: Vec<String>,
+}
+
+/// Parse the `<property>` entries of a Hadoop `*-site.xml` file.
+fn parse_hadoop_config_xml(content: &str) -> Result<Vec<(String, String)>, String> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| e.to_string())?;
+    let mut properties = vec![];
+    for property in doc
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("property"))
+    {
+        let mut name = None;
+        let mut value = String::new();
+        for child in property.children().filter(|n| n.is_element()) {
+            match child.tag_name().name() {
+                "name" => name = child.text().map(|s| s.trim().to_string()),
+                "value" => value = child.text().unwrap_or_default().trim().to_string(),
+                _ => {}
+            }
+        }
+        if let Some(name) = name {
+            if !name.is_empty() {
+                properties.push((name, value));
+            }
+        }
+    }
+    Ok(properties)
+}
+
+/// The Java system properties that Hadoop configs commonly reference, such as
+/// `hadoop.tmp.dir=/tmp/hadoop-${user.name}`, derived from the local environment.
+fn system_property(name: &str) -> Option<String> {
+    let env = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+    };
+    match name {
+        "user.name" => env(&["USER", "LOGNAME", "USERNAME"]),
+        "user.home" => env(&["HOME", "USERPROFILE"]),
+        "user.dir" => std::env::current_dir()
+            .ok()
+            .map(|d| d.to_string_lossy().to_string()),
+        "java.io.tmpdir" => Some(std::env::temp_dir().to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// Look up a single `${...}` expression the way Hadoop's `Configuration` does:
+/// `env.NAME`, `env.NAME:-default` and `env.NAME-default` read the environment,
+/// then Java system properties, and anything else is another configuration key.
+fn lookup_var(var: &str, properties: &BTreeMap<String, String>) -> Option<String> {
+    if let Some(env_expr) = var.strip_prefix("env.") {
+        if let Some((name, default)) = env_expr.split_once(":-") {
+            return match std::env::var(name) {
+                Ok(v) if !v.is_empty() => Some(v),
+                _ => Some(default.to_string()),
+            };
+        }
+        if let Some((name, default)) = env_expr.split_once('-') {
+            return Some(std::env::var(name).unwrap_or_else(|_| default.to_string()));
+        }
+        return std::env::var(env_expr).ok();
+    }
+    system_property(var).or_else(|| properties.get(var).cloned())
+}
+
+/// Expand `${var}` references in `value` recursively, `stack` holding the
+/// keys being expanded. Unresolvable references are left in place; a cycle or
+/// nesting deeper than `MAX_SUBST` is an error, as in Hadoop's `Configuration`.
+fn substitute_vars(
+    value: &str,
+    properties: &BTreeMap<String, String>,
+    unresolved: &mut Vec<String>,
+    stack: &mut Vec<String>,
+) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            expanded.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let var = &after[..end];
+        if stack.iter().any(|key| key == var) {
+            return Err(format!(
+                "Variable substitution cycle: {} -> {}",
+                stack.join(" -> "),
+                var
+            ));
+        }
+        if stack.len() >= MAX_SUBST {
+            return Err(format!(
+                "Variable substitution depth too large: {} {}",
+                MAX_SUBST,
+                stack.join(" -> ")
+            ));
+        }
+        match lookup_var(var, properties) {
+            Some(v) => {
+                stack.push(var.to_string());
+                let v = substitute_vars(&v, properties, unresolved, stack)?;
+                stack.pop();
+                expanded.push_str(&v);
+            }
+            None => {
+                if !unresolved.iter().any(|u| u == var) {
+                    unresolved.push(var.to_string());
+                }
+                expanded.push_str(&rest[start..start + 2 + end + 1]);
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn is_kept_key(key: &str) -> bool {
+    KEPT_KEY_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+/// Build an unsaved `HdfsConfig` from the files of a Hadoop client config directory.
+pub fn import_hdfs_config_dir(
+    config_dir: &str,
+    name: Option<String>,
+) -> Result<HdfsConfigImport, String> {
+    let dir = Path::new(config_dir);
+    if !dir.is_dir() {
+        return Err(format!("Config directory not found: {}", config_dir));
+    }
+
+    //后读取的文件覆盖先读取的,与hadoop加载顺序一致
+    let mut properties: BTreeMap<String, String> = BTreeMap::new();
+    let mut files = vec![];
+    for file_name in ["core-site.xml", "hdfs-site.xml"] {
+        let file_path = dir.join(file_name);
+        if !file_path.is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+        let parsed = parse_hadoop_config_xml(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", file_path.display(), e))?;
+        properties.extend(parsed);
+        files.push(file_path.to_str().unwrap_or_default().to_string());
+    }
+    if files.is_empty() {
+        return Err(format!(
+            "Neither core-site.xml nor hdfs-site.xml found in {}",
+            config_dir
+        ));
+    }
+
+    let mut unresolved_vars = vec![];
+    let mut other_config: HashMap<String, String> = HashMap::new();
+    let mut ignored_keys = vec![];
+    let mut hdfs_url = String::new();
+    for (key, value) in properties.iter() {
+        let value = substitute_vars(
+            value,
+            &properties,
+            &mut unresolved_vars,
+            &mut vec![key.clone()],
+        )
+        .map_err(|e| format!("{}: {}", key, e))?;
+        if key == DEFAULT_FS_KEY {
+            hdfs_url = value;
+        } else if is_kept_key(key) {
+            other_config.insert(key.clone(), value);
+        } else {
+            ignored_keys.push(key.clone());
+        }
+    }
+    if hdfs_url.is_empty() {
+        return Err(format!("{} is not set in {}", DEFAULT_FS_KEY, config_dir));
+    }
+
+    let krb5_conf_path = dir.join("krb5.conf");
+    if krb5_conf_path.is_file() {
+        let krb5_conf = krb5_conf_path.to_str().unwrap_or_default().to_string();
+        other_config.insert(KRB5_CONF_KEY.to_string(), krb5_conf.clone());
+        files.push(krb5_conf);
+    }
+
+    let name = name.unwrap_or_else(|| {
+        hdfs_url
+            .split("://")
+            .nth(1)
+            .unwrap_or(&hdfs_url)
+            .trim_end_matches('/')
+            .to_string()
+    });
+
+    Ok(HdfsConfigImport {
+        hdfs_config: HdfsConfig {
+            id: 0,
+            name,
+            hdfs_config: serde_json::to_string_pretty(&other_config).map_err(|e| e.to_string())?,
+            hdfs_url,
+            del_flag: 0,
+            proxy_user: String::new(),
+        },
+        files,
+        ignored_keys,
+        unresolved_vars,
+    })
+}
+
+//从core-site.xml/hdfs-site.xml导入hdfs配置
+#[tauri::command]
+pub async fn import_hdfs_config_from_dir(
+    config_dir: String,
+    name: Option<String>,
+) -> Result<HdfsConfigImport, String> {
+    import_hdfs_config_dir(&config_dir, name)
+}
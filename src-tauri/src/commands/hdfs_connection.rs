@@ -0,0 +1,618 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::hdfs_config::{get_one_hdfs_config, init_connection};
+use super::hdfs_file::build_hdfs_client;
+use crate::backend::HdfsClient;
+use crate::backend::viewfs::{is_viewfs_url, mount_table, split_target};
+
+//hdfs默认rpc端口
+pub const DEFAULT_RPC_PORT: u16 = 8020;
+//namenode web默认端口,也是webhdfs://和swebhdfs://的默认端口
+pub const DEFAULT_HTTP_PORT: u16 = 9870;
+pub const DEFAULT_HTTPS_PORT: u16 = 9871;
+//连接测试每一步的超时时间
+const TEST_STEP_TIMEOUT: Duration = Duration::from_secs(15);
+//缓存的客户端最长使用时间,超过后重建。认证失败和票据变化时也会提前重建
+const CLIENT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+//已缓存的hdfs客户端
+struct CachedClient {
+    client: Arc<HdfsClient>,
+    created: Instant,
+    //缓存时票据的到期时间,没有有效票据时为空
+    ticket_end_time: Option<i64>,
+}
+
+impl CachedClient {
+    //需要重建的原因:超过最长使用时间、认证失败、票据已续期(包括外部kinit)或已到期
+    fn stale_reason(&self, id: i64) -> Option<&'static str> {
+        if self.created.elapsed() >= CLIENT_MAX_AGE {
+            return Some("too old");
+        }
+        if self.client.auth_failed() {
+            return Some("authentication failed");
+        }
+        let cached = self.ticket_end_time?;
+        match crate::kerberos::ccache::connection_tgt_end_time(id) {
+            Some(end_time) if end_time != cached => Some("ticket renewed"),
+            Some(end_time) if end_time <= chrono::Utc::now().timestamp() => Some("ticket expired"),
+            Some(_) => None,
+            None => Some("ticket removed"),
+        }
+    }
+}
+
+static CLIENT_CACHE: Lazy<Mutex<HashMap<i64, CachedClient>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+//获取缓存的客户端,需要重建时丢弃
+pub fn get_cached_client(id: i64) -> Option<Arc<HdfsClient>> {
+    let mut cache = CLIENT_CACHE.lock().ok()?;
+    let cached = cache.get(&id)?;
+    match cached.stale_reason(id) {
+        None => Some(cached.client.clone()),
+        Some(reason) => {
+            log::info!("Hdfs client {} rebuilt: {}", id, reason);
+            cache.remove(&id);
+            None
+        }
+    }
+}
+
+//本次运行中打开过且未断开的连接,后台任务为这些连接续期kerberos票据
+static OPEN_CONNECTIONS: Lazy<Mutex<HashSet<i64>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+pub fn is_connected(id: i64) -> bool {
+    OPEN_CONNECTIONS
+        .lock()
+        .map(|open| open.contains(&id))
+        .unwrap_or(false)
+}
+
+pub fn cache_client(id: i64, client: Arc<HdfsClient>) {
+    if let Ok(mut open) = OPEN_CONNECTIONS.lock() {
+        open.insert(id);
+    }
+    if let Ok(mut cache) = CLIENT_CACHE.lock() {
+        cache.insert(
+            id,
+            CachedClient {
+                client,
+                created: Instant::now(),
+                ticket_end_time: crate::kerberos::ccache::connection_tgt_end_time(id)
+                    .filter(|end_time| *end_time > chrono::Utc::now().timestamp()),
+            },
+        );
+    }
+}
+
+//配置修改、重新登录或断开连接时丢弃缓存的客户端
+pub fn invalidate_client(id: i64) {
+    if let Ok(mut cache) = CLIENT_CACHE.lock() {
+        if cache.remove(&id).is_some() {
+            log::info!("Hdfs client {} removed from cache", id);
+        }
+    }
+}
+
+//rpc客户端的身份。hdfs-native不能按客户端指定票据缓存和代理用户,只在连接namenode时
+//(包括已有客户端断线重连和HA切换)读取环境变量KRB5CCNAME和HADOOP_PROXY_USER
+#[derive(Debug, Clone, PartialEq)]
+struct RpcIdentity {
+    ccache: Option<OsString>,
+    proxy_user: Option<OsString>,
+}
+
+//环境变量当前对应的身份,以及用各身份建立的rpc客户端
+#[derive(Default)]
+struct RpcIdentities {
+    current: Option<RpcIdentity>,
+    //全部rpc客户端,包括连接测试和探测namenode时临时建立的
+    clients: Vec<(i64, RpcIdentity, Weak<hdfs_native::Client>)>,
+}
+
+impl RpcIdentities {
+    //还在使用的其他身份的客户端所属的连接
+    fn others(&mut self, identity: &RpcIdentity) -> Vec<i64> {
+        self.clients
+            .retain(|(_, _, client)| client.strong_count() > 0);
+        let mut ids: Vec<i64> = self
+            .clients
+            .iter()
+            .filter(|(_, other, _)| other != identity)
+            .map(|(id, _, _)| *id)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+}
+
+static RPC_IDENTITIES: Lazy<Mutex<RpcIdentities>> =
+    Lazy::new(|| Mutex::new(RpcIdentities::default()));
+
+//同一时间只建立一个rpc客户端
+static CONNECTION_ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+//启动时的HADOOP_PROXY_USER,没有配置代理用户的连接继续使用
+static ORIGINAL_PROXY_USER: Lazy<Option<OsString>> =
+    Lazy::new(|| std::env::var_os("HADOOP_PROXY_USER"));
+
+//启动时的HADOOP_PROXY_USER,webhdfs连接没有配置代理用户时使用
+pub fn original_proxy_user() -> Option<String> {
+    ORIGINAL_PROXY_USER
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !p.is_empty())
+}
+
+//没有其他身份的rpc客户端时把环境变量切换到该身份,否则返回错误,不影响其他连接。
+//同一连接重新登录或修改了代理用户时,先丢弃它缓存的旧客户端
+fn switch_identity(id: i64, identity: &RpcIdentity) -> Result<(), String> {
+    let mut identities = RPC_IDENTITIES.lock().map_err(|e| e.to_string())?;
+    if identities.current.as_ref() == Some(identity) {
+        return Ok(());
+    }
+    if identities.others(identity).contains(&id) {
+        invalidate_client(id);
+    }
+    let others = identities.others(identity);
+    if !others.is_empty() {
+        return Err(format!(
+            "Connection {} is open with another Kerberos ticket cache or proxy user. RPC clients read both from the process environment, so disconnect it first, or use a webhdfs:// URL to browse both side by side",
+            others
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let _env = crate::kerberos::ccache::ENV_LOCK
+        .write()
+        .map_err(|e| e.to_string())?;
+    // Safety: no rpc client of another identity is alive, so hdfs-native is not
+    // connecting in the background, and GSSAPI calls of WebHDFS hold ENV_LOCK
+    unsafe {
+        match identity.ccache.as_ref() {
+            Some(ccache) => std::env::set_var("KRB5CCNAME", ccache),
+            None => std::env::remove_var("KRB5CCNAME"),
+        }
+        match identity.proxy_user.as_ref() {
+            Some(proxy_user) => std::env::set_var("HADOOP_PROXY_USER", proxy_user),
+            None => std::env::remove_var("HADOOP_PROXY_USER"),
+        }
+    }
+    identities.current = Some(identity.clone());
+    Ok(())
+}
+
+//使用连接的kerberos票据缓存和代理用户建立rpc客户端并完成首次rpc握手,首次rpc失败时返回错误。
+//环境变量是进程级的,不同票据缓存或代理用户的rpc客户端不能同时存在,需要同时使用时改用webhdfs://
+pub async fn new_rpc_client(
+    id: i64,
+    proxy_user: &str,
+    fs_url: &str,
+    config: &HashMap<String, String>,
+) -> Result<Arc<hdfs_native::Client>, String> {
+    let _guard = CONNECTION_ENV_LOCK.lock().await;
+    let identity = RpcIdentity {
+        ccache: crate::kerberos::ccache::connection_ccache(id),
+        proxy_user: Some(OsString::from(proxy_user))
+            .filter(|p| !p.is_empty())
+            .or_else(|| ORIGINAL_PROXY_USER.clone()),
+    };
+    switch_identity(id, &identity)?;
+    let client = Arc::new(
+        hdfs_native::Client::new_with_config(fs_url, config.clone()).map_err(|e| e.to_string())?,
+    );
+    if let Ok(mut identities) = RPC_IDENTITIES.lock() {
+        identities
+            .clients
+            .push((id, identity, Arc::downgrade(&client)));
+    }
+    client.get_file_info("/").await.map_err(|e| e.to_string())?;
+    Ok(client)
+}
+
+//断开连接
+#[tauri::command]
+pub async fn disconnect_hdfs(id: i64) -> Result<(), String> {
+    invalidate_client(id);
+    if let Ok(mut open) = OPEN_CONNECTIONS.lock() {
+        open.remove(&id);
+    }
+    Ok(())
+}
+
+//namenode地址
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct NamenodeAddress {
+    //所属文件系统,如hdfs://nameservice1。viewfs连接时为挂载目标的文件系统
+    pub fs_url: String,
+    //HA配置中的namenode名称,非HA时为空
+    pub name: String,
+    //host:port
+    pub address: String,
+}
+
+//连接测试单步结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ConnectionTestStep {
+    pub name: String,
+    pub success: bool,
+    //未配置该项时跳过
+    pub skipped: bool,
+    pub message: String,
+    pub elapsed_ms: u64,
+}
+
+//连接测试结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ConnectionTestResult {
+    pub success: bool,
+    pub steps: Vec<ConnectionTestStep>,
+    pub active_namenode: Option<String>,
+    pub user: Option<String>,
+    pub rpc_latency_ms: Option<u64>,
+}
+
+impl ConnectionTestResult {
+    fn push(&mut self, name: &str, started: Instant, result: Result<String, String>) -> bool {
+        let success = result.is_ok();
+        self.steps.push(ConnectionTestStep {
+            name: name.to_owned(),
+            success,
+            skipped: false,
+            message: result.unwrap_or_else(|e| e),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+        success
+    }
+
+    fn skip(&mut self, name: &str, message: &str) {
+        self.steps.push(ConnectionTestStep {
+            name: name.to_owned(),
+            success: true,
+            skipped: true,
+            message: message.to_owned(),
+            elapsed_ms: 0,
+        });
+    }
+}
+
+//从hdfs_url中取出host[:port]部分
+pub fn hdfs_url_authority(hdfs_url: &str) -> String {
+    let without_scheme = hdfs_url.split("://").nth(1).unwrap_or(hdfs_url);
+    without_scheme
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+//webhdfs://和swebhdfs://连接使用REST接口,其余使用rpc
+pub fn is_webhdfs_url(hdfs_url: &str) -> bool {
+    hdfs_url.starts_with("webhdfs://") || hdfs_url.starts_with("swebhdfs://")
+}
+
+//是否通过https访问namenode的web接口
+fn use_https(hdfs_url: &str, config: &HashMap<String, String>) -> bool {
+    hdfs_url.starts_with("swebhdfs://")
+        || config
+            .get("dfs.http.policy")
+            .map(|p| p.trim().eq_ignore_ascii_case("HTTPS_ONLY"))
+            .unwrap_or(false)
+}
+
+//获取连接的全部namenode地址,HA配置时返回nameservice下的每个namenode。
+//webhdfs连接没有配置rpc-address时使用web地址,viewfs连接返回全部挂载目标的namenode
+pub fn namenode_addresses(
+    hdfs_url: &str,
+    config: &HashMap<String, String>,
+) -> Vec<NamenodeAddress> {
+    if is_viewfs_url(hdfs_url) {
+        let mut fs_urls: Vec<String> = mount_table(hdfs_url, config)
+            .targets()
+            .into_iter()
+            .filter_map(|target| split_target(target).ok())
+            .map(|(fs_url, _)| fs_url)
+            .filter(|fs_url| !is_viewfs_url(fs_url))
+            .collect();
+        fs_urls.sort();
+        fs_urls.dedup();
+        return fs_urls
+            .iter()
+            .flat_map(|fs_url| namenode_addresses(fs_url, config))
+            .collect();
+    }
+    let authority = hdfs_url_authority(hdfs_url);
+    let web_key = if use_https(hdfs_url, config) {
+        "dfs.namenode.https-address"
+    } else {
+        "dfs.namenode.http-address"
+    };
+    if let Some(namenodes) = config.get(&format!("dfs.ha.namenodes.{}", &authority)) {
+        return namenodes
+            .split(',')
+            .map(|nn| nn.trim())
+            .filter(|nn| !nn.is_empty())
+            .map(|nn| NamenodeAddress {
+                fs_url: hdfs_url.to_string(),
+                name: nn.to_string(),
+                address: config
+                    .get(&format!("dfs.namenode.rpc-address.{}.{}", &authority, nn))
+                    .or_else(|| {
+                        config
+                            .get(&format!("{}.{}.{}", web_key, &authority, nn))
+                            .filter(|_| is_webhdfs_url(hdfs_url))
+                    })
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect();
+    }
+    let default_port = match hdfs_url {
+        _ if hdfs_url.starts_with("swebhdfs://") => DEFAULT_HTTPS_PORT,
+        _ if hdfs_url.starts_with("webhdfs://") => DEFAULT_HTTP_PORT,
+        _ => DEFAULT_RPC_PORT,
+    };
+    let address = if authority.contains(':') {
+        authority
+    } else {
+        format!("{}:{}", authority, default_port)
+    };
+    vec![NamenodeAddress {
+        fs_url: hdfs_url.to_string(),
+        name: String::new(),
+        address,
+    }]
+}
+
+//namenode的web地址,优先使用配置的http(s)-address,否则用rpc地址的主机和默认端口。
+//非HA的webhdfs连接直接使用hdfs_url中的地址
+pub fn namenode_http_address(
+    namenode: &NamenodeAddress,
+    config: &HashMap<String, String>,
+) -> String {
+    let hdfs_url = namenode.fs_url.as_str();
+    let (scheme, key, default_port) = if use_https(hdfs_url, config) {
+        ("https", "dfs.namenode.https-address", DEFAULT_HTTPS_PORT)
+    } else {
+        ("http", "dfs.namenode.http-address", DEFAULT_HTTP_PORT)
+    };
+    if is_webhdfs_url(hdfs_url) && namenode.name.is_empty() {
+        return format!("{}://{}", scheme, namenode.address);
+    }
+    let rpc_host = namenode
+        .address
+        .rsplit_once(':')
+        .map(|(host, _)| host)
+        .unwrap_or(&namenode.address);
+    let configured = if namenode.name.is_empty() {
+        config.get(key)
+    } else {
+        let nameservice = hdfs_url_authority(hdfs_url);
+        config.get(&format!("{}.{}.{}", key, nameservice, namenode.name))
+    };
+    let address = match configured.and_then(|a| a.rsplit_once(':')) {
+        //0.0.0.0表示监听全部网卡,改用rpc地址的主机
+        Some(("0.0.0.0", port)) => format!("{}:{}", rpc_host, port),
+        Some((host, port)) => format!("{}:{}", host, port),
+        None => format!("{}:{}", rpc_host, default_port),
+    };
+    format!("{}://{}", scheme, address)
+}
+
+//webhdfs连接的全部namenode web地址,HA时按配置顺序故障转移
+pub fn webhdfs_endpoints(hdfs_url: &str, config: &HashMap<String, String>) -> Vec<String> {
+    namenode_addresses(hdfs_url, config)
+        .iter()
+        .filter(|nn| !nn.address.is_empty())
+        .map(|nn| namenode_http_address(nn, config))
+        .collect()
+}
+
+async fn with_timeout<T>(
+    future: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    tokio::time::timeout(TEST_STEP_TIMEOUT, future)
+        .await
+        .map_err(|_| format!("timed out after {}s", TEST_STEP_TIMEOUT.as_secs()))?
+}
+
+//直连单个namenode判断是否active
+async fn probe_namenode(
+    id: i64,
+    proxy_user: &str,
+    address: &str,
+    config: &HashMap<String, String>,
+) -> Result<(), String> {
+    new_rpc_client(id, proxy_user, &format!("hdfs://{}", address), config)
+        .await
+        .map(|_| ())
+}
+
+//连接认证的用户,kerberos时读取票据缓存的principal
+fn authenticated_user(
+    id: i64,
+    hdfs_url: &str,
+    config: &HashMap<String, String>,
+) -> Result<String, String> {
+    if config.contains_key("dfs.namenode.kerberos.principal") {
+        let ccache = crate::kerberos::ccache::connection_ccache(id);
+        let path = crate::kerberos::ccache::resolve_ccache_name(ccache.as_ref())?;
+        return crate::kerberos::ccache::read_ccache(&path)
+            .map(|cache| cache.default_principal.to_string());
+    }
+    let token = config.get(crate::backend::webhdfs::DELEGATION_TOKEN_KEY);
+    if is_webhdfs_url(hdfs_url) && token.is_some_and(|t| !t.is_empty()) {
+        return Ok("owner of the delegation token".to_string());
+    }
+    ["HADOOP_USER_NAME", "HDFS_USERNAME", "USER", "USERNAME"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+        .ok_or_else(|| "no hdfs username found".to_owned())
+}
+
+//测试hdfs连接,逐步返回每一步的结果
+#[tauri::command]
+pub async fn test_hdfs_connection(id: i64) -> Result<ConnectionTestResult, String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut result = ConnectionTestResult::default();
+
+    let started = Instant::now();
+    let hc = match get_one_hdfs_config(id).await {
+        Ok(hc) => hc,
+        Err(e) => {
+            result.push("config", started, Err(e));
+            return Ok(result);
+        }
+    };
+    let config = match serde_json::from_str::<HashMap<String, String>>(&hc.hdfs_config) {
+        Ok(config) => config,
+        Err(e) if hc.hdfs_config.trim().is_empty() => {
+            log::debug!("empty hdfs config: {}", e);
+            HashMap::new()
+        }
+        Err(e) => {
+            result.push(
+                "config",
+                started,
+                Err(format!("Invalid config json: {}", e)),
+            );
+            return Ok(result);
+        }
+    };
+    result.push("config", started, Ok(hc.hdfs_url.clone()));
+
+    //解析每个namenode地址
+    let namenodes = namenode_addresses(&hc.hdfs_url, &config);
+    let mut dns_ok = true;
+    for nn in namenodes.iter() {
+        let started = Instant::now();
+        let step_name = format!(
+            "dns {}",
+            if nn.name.is_empty() {
+                &nn.address
+            } else {
+                &nn.name
+            }
+        );
+        let resolved = if nn.address.is_empty() {
+            Err(format!(
+                "dfs.namenode.rpc-address.{}.{} is not set",
+                hdfs_url_authority(&nn.fs_url),
+                &nn.name
+            ))
+        } else {
+            with_timeout(async {
+                tokio::net::lookup_host(&nn.address)
+                    .await
+                    .map_err(|e| format!("{}: {}", &nn.address, e))
+                    .map(|addrs| {
+                        addrs
+                            .map(|a| a.ip().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+            })
+            .await
+            .map(|ips| format!("{} -> {}", &nn.address, ips))
+        };
+        dns_ok &= result.push(&step_name, started, resolved);
+    }
+
+    //kerberos登录
+    if config.contains_key("dfs.namenode.kerberos.principal") {
+        let started = Instant::now();
+        let kinit = init_connection(id, None, None)
+            .await
+            .map(|_| config["dfs.namenode.kerberos.principal"].clone());
+        result.push("kerberos", started, kinit);
+    } else {
+        result.skip("kerberos", "no kerberos principal configured");
+    }
+
+    //rpc往返
+    let started = Instant::now();
+    let rpc = with_timeout(async {
+        let client = build_hdfs_client(id).await?;
+        client.get_file_info("/").await
+    })
+    .await;
+    let rpc_ok = result.push(
+        "rpc",
+        started,
+        rpc.map(|_| format!("{}ms", started.elapsed().as_millis())),
+    );
+    if rpc_ok {
+        result.rpc_latency_ms = Some(started.elapsed().as_millis() as u64);
+    }
+
+    //HA时逐个探测active namenode,webhdfs由客户端自动切换到active namenode
+    let rpc_namenodes: Vec<&NamenodeAddress> = namenodes
+        .iter()
+        .filter(|nn| !nn.address.is_empty() && !is_webhdfs_url(&nn.fs_url))
+        .collect();
+    if namenodes.len() > 1 && rpc_namenodes.is_empty() {
+        result.skip(
+            "active namenode",
+            "WebHDFS fails over to the active namenode automatically",
+        );
+    } else if namenodes.len() > 1 {
+        let started = Instant::now();
+        let mut states = vec![];
+        for nn in rpc_namenodes {
+            match with_timeout(probe_namenode(id, &hc.proxy_user, &nn.address, &config)).await {
+                Ok(()) => {
+                    if result.active_namenode.is_none() {
+                        result.active_namenode = Some(nn.name.clone());
+                    }
+                    states.push(format!("{} ({}): active", &nn.name, &nn.address));
+                }
+                Err(e) if e.contains("StandbyException") => {
+                    states.push(format!("{} ({}): standby", &nn.name, &nn.address))
+                }
+                Err(e) => states.push(format!("{} ({}): {}", &nn.name, &nn.address, e)),
+            }
+        }
+        let states = states.join("; ");
+        let active = if result.active_namenode.is_some() {
+            Ok(states)
+        } else {
+            Err(format!("no active namenode found: {}", states))
+        };
+        result.push("active namenode", started, active);
+    } else if rpc_ok {
+        result.active_namenode = namenodes.first().map(|nn| nn.address.clone());
+    }
+
+    //认证用户:kerberos连接为票据缓存中的principal,否则为simple认证发送的用户名;
+    //配置了代理用户时rpc以代理用户身份执行
+    let started = Instant::now();
+    let user = authenticated_user(id, &hc.hdfs_url, &config);
+    let effective_user = Some(hc.proxy_user.clone())
+        .filter(|p| !p.is_empty())
+        .or_else(original_proxy_user);
+    result.user = match (&effective_user, &user) {
+        (Some(proxy_user), _) => Some(proxy_user.clone()),
+        (None, Ok(user)) => Some(user.clone()),
+        (None, Err(_)) => None,
+    };
+    let user = user.map(|user| match effective_user {
+        Some(proxy_user) => format!("{} as {}", user, proxy_user),
+        None => user,
+    });
+    result.push("user", started, user);
+
+    result.success = dns_ok && rpc_ok && result.steps.iter().all(|s| s.success);
+    Ok(result)
+}
@@ -1,15 +1,40 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use bytes::Bytes;
-use hdfs_native::{Client, WriteOptions};
+use bytes::{Bytes, BytesMut};
+use hdfs_native::WriteOptions;
 use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::backend::{
+    FileStatus, HdfsClient, HdfsFileReader, HdfsFileWriter, RpcClient, is_file_not_found,
+};
+use crate::backend::viewfs::{ViewFsClient, is_viewfs_url, mount_table, split_target};
+use crate::backend::webhdfs::{DELEGATION_TOKEN_KEY, WebHdfsAuth, WebHdfsClient};
 
 use super::hdfs_config::{get_hdfs_username, HdfsConfig};
+use super::hdfs_transfer::Transfer;
+use futures::StreamExt;
 use futures::future::BoxFuture;
 use futures_util::FutureExt;
-use std::fs::File;
-use std::io::{BufReader, Read, Write};
-use std::path::Path;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+//上传时每次读取和写入的块大小
+const UPLOAD_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+//冲突时改名最多尝试的后缀数
+const MAX_RENAME_SUFFIX: usize = 1000;
+//下载时每次读取的块大小
+const DOWNLOAD_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+//未下载完的文件的后缀,长度一致后改名
+const PARTIAL_SUFFIX: &str = ".part";
+//记录.part文件对应的hdfs文件长度和修改时间,不一致时不续传
+const PARTIAL_SOURCE_SUFFIX: &str = ".part.source";
+//写入hdfs时先写到该后缀的临时文件,写完后才改名为目标文件,失败时不影响已有的目标文件
+const COPYING_SUFFIX: &str = "._COPYING_";
+
 //hdfs配置
 #[derive(Debug, Default, Deserialize, Serialize, sqlx::FromRow, Clone)]
 pub struct HdfsFile {
@@ -30,7 +55,18 @@ pub struct HdfsFile {
     pub space_consumed: Option<u64>,
     pub space_quota: Option<u64>,
 }
-pub async fn get_hdfs_client(id: i64) -> Result<hdfs_native::Client, String> {
+//获取hdfs客户端,优先使用缓存
+pub async fn get_hdfs_client(id: i64) -> Result<Arc<HdfsClient>, String> {
+    if let Some(client) = super::hdfs_connection::get_cached_client(id) {
+        return Ok(client);
+    }
+    let client = Arc::new(build_hdfs_client(id).await?);
+    super::hdfs_connection::cache_client(id, client.clone());
+    Ok(client)
+}
+
+//新建hdfs客户端
+pub async fn build_hdfs_client(id: i64) -> Result<HdfsClient, String> {
     let hdfs_config_instance: HdfsConfig =
         crate::commands::hdfs_config::get_one_hdfs_config(id).await?;
 
@@ -39,12 +75,89 @@ pub async fn get_hdfs_client(id: i64) -> Result<hdfs_native::Client, String> {
             .unwrap_or_default();
 
     let hdfs_url = hdfs_config_instance.hdfs_url;
-    /*TODO 自动kerberos登录*/
+    let proxy_user = &hdfs_config_instance.proxy_user;
+    if is_viewfs_url(&hdfs_url) {
+        return build_viewfs_client(id, &hdfs_url, proxy_user, &other_config)
+            .await
+            .map(HdfsClient::ViewFs);
+    }
+    build_fs_client(id, &hdfs_url, proxy_user, &other_config).await
+}
+
+//新建hdfs://或webhdfs://文件系统的客户端
+async fn build_fs_client(
+    id: i64,
+    hdfs_url: &str,
+    proxy_user: &str,
+    config: &HashMap<String, String>,
+) -> Result<HdfsClient, String> {
+    if super::hdfs_connection::is_webhdfs_url(hdfs_url) {
+        return build_webhdfs_client(id, hdfs_url, proxy_user, config).map(HdfsClient::WebHdfs);
+    }
 
-    let client =
-        hdfs_native::Client::new_with_config(&hdfs_url, other_config).map_err(|e| e.to_string())?;
+    //使用该连接自己的kerberos票据缓存和代理用户完成首次rpc握手,失败时不缓存
+    let client = super::hdfs_connection::new_rpc_client(id, proxy_user, hdfs_url, config).await?;
+    Ok(HdfsClient::Rpc(RpcClient::new(client)))
+}
 
-    return Ok(client);
+//新建viewfs客户端,挂载到同一个文件系统的挂载点共用一个客户端
+async fn build_viewfs_client(
+    id: i64,
+    hdfs_url: &str,
+    proxy_user: &str,
+    config: &HashMap<String, String>,
+) -> Result<ViewFsClient, String> {
+    let table = mount_table(hdfs_url, config);
+    if table.links.is_empty() && table.fallback.is_none() {
+        return Err(format!("No {}link.* configured", &table.prefix));
+    }
+    let mut clients: HashMap<String, Arc<HdfsClient>> = HashMap::new();
+    let mut targets = vec![];
+    for target in table.targets() {
+        let (fs_url, path) = split_target(target)?;
+        if !clients.contains_key(&fs_url) {
+            let client = build_fs_client(id, &fs_url, proxy_user, config).await?;
+            clients.insert(fs_url.clone(), Arc::new(client));
+        }
+        targets.push((path, clients[&fs_url].clone()));
+    }
+    let fallback = match table.fallback {
+        Some(_) => targets.pop(),
+        None => None,
+    };
+    let links = table
+        .links
+        .into_iter()
+        .zip(targets)
+        .map(|(link, (path, client))| (link.path, path, client))
+        .collect();
+    Ok(ViewFsClient::new(links, fallback))
+}
+
+//新建webhdfs客户端。配置了委托令牌时使用令牌,配置了kerberos时使用SPNEGO,否则使用simple认证
+fn build_webhdfs_client(
+    id: i64,
+    hdfs_url: &str,
+    proxy_user: &str,
+    config: &HashMap<String, String>,
+) -> Result<WebHdfsClient, String> {
+    let endpoints = super::hdfs_connection::webhdfs_endpoints(hdfs_url, config);
+    let token = config.get(DELEGATION_TOKEN_KEY).filter(|t| !t.is_empty());
+    let auth = if let Some(token) = token {
+        WebHdfsAuth::Token(token.clone())
+    } else if config.contains_key("dfs.namenode.kerberos.principal") {
+        WebHdfsAuth::Kerberos(crate::kerberos::ccache::connection_ccache(id))
+    } else {
+        let user = ["HADOOP_USER_NAME", "HDFS_USERNAME", "USER", "USERNAME"]
+            .iter()
+            .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+            .ok_or_else(|| "no hdfs username found".to_owned())?;
+        WebHdfsAuth::Simple(user)
+    };
+    let doas = Some(proxy_user.to_string())
+        .filter(|p| !p.is_empty())
+        .or_else(super::hdfs_connection::original_proxy_user);
+    WebHdfsClient::new(endpoints, auth, doas)
 }
 //获取hdfs文件列表
 #[tauri::command]
@@ -55,10 +168,7 @@ pub async fn get_hdfs_file_list(
 ) -> Result<Vec<HdfsFile>, String> {
     //log::info!("get_hdfs_file_list:parent_path:{}", &parent_path);
     let client = get_hdfs_client(id).await?;
-    let files = client
-        .list_status(&parent_path, false)
-        .await
-        .map_err(|e| e.to_string())?;
+    let files = client.list_status(&parent_path, false).await?;
 
     let mut hdfs_files = Vec::new();
 
@@ -87,10 +197,7 @@ pub async fn get_hdfs_file_list(
         };
 
         if file.isdir && show_content_summary {
-            let content_summary: hdfs_native::client::ContentSummary = client
-                .get_content_summary(&file.path)
-                .await
-                .map_err(|e| e.to_string())?;
+            let content_summary = client.get_content_summary(&file.path).await?;
             // 更新hdfs_file的内容摘要字段
             hdfs_file.length = content_summary.length as usize;
             hdfs_file.file_count = Some(content_summary.file_count);
@@ -110,10 +217,7 @@ pub async fn get_hdfs_file_list(
 #[tauri::command]
 pub async fn get_hdfs_file(id: i64, file_path: String) -> Result<HdfsFile, String> {
     let client = get_hdfs_client(id).await?;
-    let file = client
-        .get_file_info(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let file = client.get_file_info(&file_path).await?;
 
     //log::info!("get_hdfs_file_list:files:{:?}", &files);
     let hdfs_file = HdfsFile {
@@ -145,14 +249,152 @@ pub async fn get_hdfs_file(id: i64, file_path: String) -> Result<HdfsFile, Strin
     //log::info!("get_hdfs_file_list:hdfsFiles:{:?}", &hdfs_files);
     Ok(hdfs_file)
 }
-//上传文件
+//目标文件已存在时的处理方式
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    #[default]
+    Fail,
+    Overwrite,
+    Skip,
+    //加_1、_2等后缀另存
+    Rename,
+    //大小和修改时间都相同时跳过,否则覆盖,上传后保留本地文件的修改时间
+    SkipIfSame,
+}
+
+//写入选项,permission和umask为八进制数值,只能设置其中一个
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct UploadOptions {
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+    pub replication: Option<u32>,
+    pub block_size: Option<u64>,
+    pub permission: Option<u32>,
+    pub umask: Option<u32>,
+}
+
+impl UploadOptions {
+    pub fn check(&self) -> Result<(), String> {
+        if self.permission.is_some() && self.umask.is_some() {
+            return Err("Set either permission or umask, not both".to_string());
+        }
+        if self.permission.is_some_and(|p| p > 0o7777) {
+            return Err(format!("Invalid permission {:o}", self.permission.unwrap_or_default()));
+        }
+        if self.umask.is_some_and(|u| u > 0o777) {
+            return Err(format!("Invalid umask {:o}", self.umask.unwrap_or_default()));
+        }
+        if self.replication == Some(0) {
+            return Err("Replication must be at least 1".to_string());
+        }
+        //块大小必须是校验块(512字节)的整数倍
+        if self.block_size.is_some_and(|b| b == 0 || b % 512 != 0) {
+            return Err(format!(
+                "Block size {} is not a multiple of 512",
+                self.block_size.unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+
+    fn write_options(&self, overwrite: bool) -> WriteOptions {
+        let mut write_options = WriteOptions::default().overwrite(overwrite);
+        if let Some(replication) = self.replication {
+            write_options = write_options.replication(replication);
+        }
+        if let Some(block_size) = self.block_size {
+            write_options = write_options.block_size(block_size);
+        }
+        if let Some(permission) = self.permission.or(self.umask.map(|u| 0o666 & !u)) {
+            write_options = write_options.permission(permission);
+        }
+        write_options
+    }
+
+    //新建目录的权限
+    fn dir_permission(&self) -> u32 {
+        self.umask.map(|u| 0o777 & !u).unwrap_or(0o755)
+    }
+}
+
+//文件名加后缀,a.txt加1为a_1.txt
+fn suffixed_path(path: &str, n: usize) -> String {
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, extension) = match name.rfind('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    };
+    format!("{}/{}_{}{}", parent, stem, n, extension)
+}
+
+//按冲突策略确定写入的路径和是否覆盖,跳过时返回None
+//local为本地文件的大小和修改时间(毫秒)
+async fn resolve_target(
+    client: &HdfsClient,
+    target_path: &str,
+    options: &UploadOptions,
+    local: Option<(u64, Option<u64>)>,
+) -> Result<Option<(String, bool)>, String> {
+    let existing = match client.get_file_info(target_path).await {
+        Ok(existing) => existing,
+        Err(e) if is_file_not_found(&e) => return Ok(Some((target_path.to_string(), false))),
+        Err(e) => return Err(e),
+    };
+    //先写临时文件,已存在时要在写入前报错
+    if options.conflict == ConflictPolicy::Fail {
+        return Err(format!(
+            "FileAlreadyExistsException: {} already exists",
+            target_path
+        ));
+    }
+    if existing.isdir && options.conflict != ConflictPolicy::Skip {
+        return Err(format!("{} is a directory", target_path));
+    }
+    match options.conflict {
+        ConflictPolicy::Fail | ConflictPolicy::Overwrite => {
+            Ok(Some((target_path.to_string(), true)))
+        }
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::SkipIfSame => {
+            let same = local.is_some_and(|(length, mtime)| {
+                existing.length as u64 == length && mtime == Some(existing.modification_time)
+            });
+            Ok((!same).then(|| (target_path.to_string(), true)))
+        }
+        ConflictPolicy::Rename => Ok(Some((free_path(client, target_path).await?, false))),
+    }
+}
+
+//第一个不存在的加后缀的路径
+async fn free_path(client: &HdfsClient, path: &str) -> Result<String, String> {
+    for n in 1..=MAX_RENAME_SUFFIX {
+        let candidate = suffixed_path(path, n);
+        match client.get_file_info(&candidate).await {
+            Ok(_) => continue,
+            Err(e) if is_file_not_found(&e) => return Ok(candidate),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(format!(
+        "No free name for {} after {} tries",
+        path, MAX_RENAME_SUFFIX
+    ))
+}
+
+//上传文件,transfer_id用于进度事件和取消,返回实际写入的路径,跳过时为空
 #[tauri::command]
 pub async fn upload_hdfs_file(
+    app: AppHandle,
     id: i64,
     parent_path: String,
     local_file_path: String,
-) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    transfer_id: String,
+    upload_options: Option<UploadOptions>,
+) -> Result<Option<String>, String> {
+    let upload_options = upload_options.unwrap_or_default();
+    upload_options.check()?;
+    let client = get_hdfs_client(id).await?;
     //获取文件名
     let local_file_name = std::path::Path::new(&local_file_path)
         .file_name()
@@ -160,91 +402,495 @@ pub async fn upload_hdfs_file(
         .to_str()
         .unwrap_or_default()
         .to_string();
+    let target_path = format!("{}/{}", parent_path.trim_end_matches('/'), &local_file_name);
 
-    let local_file = File::open(&local_file_path).map_err(|e| e.to_string())?;
-    let mut local_file_buf_reader = BufReader::new(local_file);
+    let total = tokio::fs::metadata(&local_file_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+    let transfer = Transfer::start(
+        app,
+        &transfer_id,
+        id,
+        &local_file_path,
+        &target_path,
+        total,
+        1,
+    )?;
+    let result = upload_local_file(
+        &client,
+        &local_file_path,
+        &target_path,
+        &upload_options,
+        &transfer,
+    )
+    .await;
+    transfer.finish(result)
+}
 
-    let mut hdfs_file_writer = client
-        .create(
-            &format!("{}/{}", &parent_path, &local_file_name),
-            WriteOptions::default(),
-        )
+//把本地文件写入hdfs,返回实际写入的路径,按冲突策略跳过时返回None。
+//先写入临时文件再改名,取消或失败时只删除临时文件
+async fn upload_local_file(
+    client: &HdfsClient,
+    local_file_path: &str,
+    target_path: &str,
+    upload_options: &UploadOptions,
+    transfer: &Transfer,
+) -> Result<Option<String>, String> {
+    transfer.check_cancelled()?;
+    let metadata = tokio::fs::metadata(local_file_path)
         .await
         .map_err(|e| e.to_string())?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64);
+    let resolved =
+        resolve_target(client, target_path, upload_options, Some((metadata.len(), mtime))).await?;
+    let Some((target_path, overwrite)) = resolved else {
+        transfer.advance(metadata.len());
+        transfer.file_done();
+        return Ok(None);
+    };
+    let target_path = target_path.as_str();
 
+    let mut local_file = tokio::fs::File::open(local_file_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let copying_path = format!("{}{}", target_path, COPYING_SUFFIX);
+    let mut hdfs_file_writer = client
+        .create(&copying_path, upload_options.write_options(true))
+        .await?;
+
+    let result = match copy_to_hdfs(&mut local_file, &mut hdfs_file_writer, transfer).await {
+        Ok(()) => hdfs_file_writer.close().await,
+        Err(e) => {
+            //先关闭再删除,否则文件租约要等到超时才释放
+            if let Err(close_error) = hdfs_file_writer.close().await {
+                log::warn!("close {} error :{}", &copying_path, close_error);
+            }
+            Err(e)
+        }
+    };
+    if let Err(e) = result {
+        delete_copying(client, &copying_path).await;
+        return Err(e);
+    }
+    //保留修改时间,下次上传时才能比较。改名不改变修改时间
+    if upload_options.conflict == ConflictPolicy::SkipIfSame {
+        let set_times = client.set_times(&copying_path, mtime, None).await;
+        if let Err(e) = set_times {
+            log::warn!("set times of {} error :{}", &copying_path, e);
+        }
+    }
+    rename_copying(client, &copying_path, target_path, overwrite).await?;
+    transfer.file_done();
+    Ok(Some(target_path.to_string()))
+}
+
+//写完的临时文件改名为目标文件,overwrite时替换已有的目标文件,改名失败时删除临时文件
+async fn rename_copying(
+    client: &HdfsClient,
+    copying_path: &str,
+    target_path: &str,
+    overwrite: bool,
+) -> Result<(), String> {
+    let renamed = client.rename(copying_path, target_path, overwrite).await;
+    if let Err(e) = renamed {
+        delete_copying(client, copying_path).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+//删除没有写完或没能改名的临时文件
+async fn delete_copying(client: &HdfsClient, copying_path: &str) {
+    if let Err(e) = client.delete(copying_path, false).await {
+        log::warn!("delete partial file {} error :{}", copying_path, e);
+    }
+}
+
+//按UPLOAD_BUFFER_SIZE分块写入,每块之间检查是否取消
+async fn copy_to_hdfs(
+    local_file: &mut tokio::fs::File,
+    hdfs_file_writer: &mut HdfsFileWriter,
+    transfer: &Transfer,
+) -> Result<(), String> {
     loop {
-        let mut buf = [0u8; 1024];
-        if let Ok(nbytes_read) = local_file_buf_reader.read(&mut buf[..]) {
-            //log::info!("nbytes_read:{}", nbytes_read);
+        transfer.check_cancelled()?;
+        let mut buf = BytesMut::with_capacity(UPLOAD_BUFFER_SIZE);
+        while buf.len() < UPLOAD_BUFFER_SIZE {
+            let nbytes_read = local_file
+                .read_buf(&mut buf)
+                .await
+                .map_err(|e| e.to_string())?;
             // 如果没有字节可读，跳出循环
             if nbytes_read == 0 {
                 break;
             }
-            let s = &buf[0..nbytes_read];
-            let b = bytes::Bytes::copy_from_slice(s);
-            // 从buffer构造字符串
-            let _write_size = hdfs_file_writer.write(b).await.map_err(|e| e.to_string())?;
-            //log::info!("writeSize:{}", writeSize);
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let len = buf.len() as u64;
+        hdfs_file_writer.write(buf.freeze()).await?;
+        transfer.advance(len);
+    }
+}
+
+//上传目录的选项,include/exclude为glob,不含/时匹配文件名,否则匹配相对路径
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct FolderUploadOptions {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub skip_hidden: bool,
+    //同时上传的文件数
+    pub concurrency: Option<usize>,
+    //不再上传的相对路径,传输队列续传时跳过之前已上传的文件
+    #[serde(default)]
+    pub skip_paths: Vec<String>,
+}
+
+//单个文件的上传结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct FileUploadResult {
+    pub local_path: String,
+    //按冲突策略改名后的路径
+    pub target_path: String,
+    pub length: u64,
+    //目标已存在,按冲突策略跳过
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+//目录上传结果,skipped为被过滤掉的文件和目录数加上按冲突策略跳过的文件数
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct FolderUploadReport {
+    pub uploaded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub bytes: u64,
+    pub files: Vec<FileUploadResult>,
+}
+
+//include/exclude过滤
+struct UploadFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+    skip_hidden: bool,
+}
+
+impl UploadFilter {
+    fn new(options: &FolderUploadOptions) -> Result<UploadFilter, String> {
+        let compile = |patterns: &Vec<String>| {
+            patterns
+                .iter()
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .map(|p| glob::Pattern::new(p).map_err(|e| format!("invalid glob {} :{}", p, e)))
+                .collect::<Result<Vec<_>, String>>()
+        };
+        Ok(UploadFilter {
+            include: compile(&options.include)?,
+            exclude: compile(&options.exclude)?,
+            skip_hidden: options.skip_hidden,
+        })
+    }
+
+    fn matches(pattern: &glob::Pattern, name: &str, relative_path: &str) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        if pattern.as_str().contains('/') {
+            pattern.matches_with(relative_path, options)
         } else {
-            break;
+            pattern.matches_with(name, options)
         }
     }
-    hdfs_file_writer.close().await.map_err(|e| e.to_string())?;
 
-    Ok(true)
+    //隐藏或被exclude的文件和目录整个跳过
+    fn skip(&self, name: &str, relative_path: &str) -> bool {
+        (self.skip_hidden && name.starts_with('.'))
+            || self
+                .exclude
+                .iter()
+                .any(|p| Self::matches(p, name, relative_path))
+    }
+
+    //include只过滤文件,为空时上传全部文件
+    fn include(&self, name: &str, relative_path: &str) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|p| Self::matches(p, name, relative_path))
+    }
+}
+
+//本地目录中要上传的文件和本地就为空的目录,路径相对于上传的目录,以/分隔。
+//读取失败的文件和子目录记在failed中,不影响其他文件
+#[derive(Default)]
+struct LocalFolder {
+    empty_dirs: Vec<String>,
+    files: Vec<(String, u64)>,
+    failed: Vec<(String, String)>,
+    skipped: usize,
+}
+
+//遍历本地目录,不进入指向目录的符号链接。只有上传的目录本身读取失败时返回错误
+fn walk_local_folder(root: &Path, filter: &UploadFilter) -> Result<LocalFolder, String> {
+    let mut folder = LocalFolder::default();
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(root.join(&dir)) {
+            Ok(entries) => entries,
+            Err(e) if dir.is_empty() => {
+                return Err(format!("read folder {} error :{}", root.display(), e));
+            }
+            Err(e) => {
+                folder
+                    .failed
+                    .push((dir, format!("read folder error :{}", e)));
+                continue;
+            }
+        };
+        let mut empty = true;
+        for entry in entries {
+            empty = false;
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    folder
+                        .failed
+                        .push((dir.clone(), format!("read folder error :{}", e)));
+                    continue;
+                }
+            };
+            let name = entry.file_name().to_string_lossy().to_string();
+            let relative_path = if dir.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", dir, name)
+            };
+            if filter.skip(&name, &relative_path) {
+                folder.skipped += 1;
+                continue;
+            }
+            //损坏的符号链接、没有权限等
+            let metadata = entry
+                .file_type()
+                .and_then(|file_type| Ok((file_type, std::fs::metadata(entry.path())?)));
+            let (file_type, metadata) = match metadata {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    folder
+                        .failed
+                        .push((relative_path, format!("read error :{}", e)));
+                    continue;
+                }
+            };
+            if metadata.is_dir() && file_type.is_symlink() {
+                folder.skipped += 1;
+            } else if metadata.is_dir() {
+                pending.push(relative_path);
+            } else if filter.include(&name, &relative_path) {
+                folder.files.push((relative_path, metadata.len()));
+            } else {
+                folder.skipped += 1;
+            }
+        }
+        //只有被过滤掉的条目的目录不建,上传目录本身总是会建
+        if empty && !dir.is_empty() {
+            folder.empty_dirs.push(dir);
+        }
+    }
+    folder.files.sort();
+    Ok(folder)
+}
+
+//上传本地目录到parent_path下,保留目录结构,返回每个文件的结果
+#[tauri::command]
+pub async fn upload_hdfs_folder(
+    app: AppHandle,
+    id: i64,
+    parent_path: String,
+    local_folder_path: String,
+    transfer_id: String,
+    options: FolderUploadOptions,
+    upload_options: Option<UploadOptions>,
+) -> Result<FolderUploadReport, String> {
+    let upload_options = upload_options.unwrap_or_default();
+    upload_options.check()?;
+    let client = get_hdfs_client(id).await?;
+    let filter = UploadFilter::new(&options)?;
+    let local_root = PathBuf::from(&local_folder_path);
+    let local_folder_name = local_root
+        .file_name()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or_default()
+        .to_string();
+    let target_root = format!("{}/{}", parent_path.trim_end_matches('/'), &local_folder_name);
+
+    let walk_root = local_root.clone();
+    let mut folder = tokio::task::spawn_blocking(move || walk_local_folder(&walk_root, &filter))
+        .await
+        .map_err(|e| e.to_string())??;
+    let skip_paths: HashSet<&String> = options.skip_paths.iter().collect();
+    let found = folder.files.len();
+    folder
+        .files
+        .retain(|(relative_path, _)| !skip_paths.contains(relative_path));
+    folder.skipped += found - folder.files.len();
+    let total = folder.files.iter().map(|(_, length)| length).sum();
+    let transfer = Transfer::start(
+        app,
+        &transfer_id,
+        id,
+        &local_folder_path,
+        &target_root,
+        total,
+        folder.files.len() as u64,
+    )?;
+
+    //先建目录,只建有文件要上传的目录和本地就为空的目录
+    let mut dirs: BTreeSet<&str> = folder.empty_dirs.iter().map(|dir| dir.as_str()).collect();
+    for (relative_path, _) in &folder.files {
+        let mut path = relative_path.as_str();
+        while let Some((parent, _)) = path.rsplit_once('/') {
+            dirs.insert(parent);
+            path = parent;
+        }
+    }
+    let target_dirs: Vec<String> = std::iter::once(target_root.clone())
+        .chain(dirs.iter().map(|dir| format!("{}/{}", &target_root, dir)))
+        .collect();
+    let created: Result<(), String> = async {
+        for target_dir in target_dirs {
+            transfer.check_cancelled()?;
+            client
+                .mkdirs(&target_dir, upload_options.dir_permission(), true)
+                .await?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = created {
+        return transfer.finish(Err(e));
+    }
+
+    let concurrency = options.concurrency.unwrap_or(4).clamp(1, 16);
+    let files: Vec<FileUploadResult> = futures::stream::iter(folder.files)
+        .map(|(relative_path, length)| {
+            let local_path = local_root.join(&relative_path).to_string_lossy().to_string();
+            let target_path = format!("{}/{}", &target_root, &relative_path);
+            let client = &client;
+            let upload_options = &upload_options;
+            let transfer = &transfer;
+            async move {
+                let result =
+                    upload_local_file(client, &local_path, &target_path, upload_options, transfer)
+                        .await;
+                let mut file = FileUploadResult {
+                    local_path,
+                    target_path,
+                    length,
+                    ..Default::default()
+                };
+                match result {
+                    Ok(Some(written_path)) => file.target_path = written_path,
+                    Ok(None) => file.skipped = true,
+                    Err(e) => file.error = Some(e),
+                }
+                file
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let unreadable = folder
+        .failed
+        .into_iter()
+        .map(|(relative_path, error)| FileUploadResult {
+            local_path: local_root
+                .join(&relative_path)
+                .to_string_lossy()
+                .to_string(),
+            target_path: format!("{}/{}", &target_root, &relative_path),
+            error: Some(error),
+            ..Default::default()
+        });
+    let mut report = FolderUploadReport {
+        skipped: folder.skipped,
+        ..Default::default()
+    };
+    for file in files.into_iter().chain(unreadable) {
+        if file.error.is_some() {
+            report.failed += 1;
+        } else if file.skipped {
+            report.skipped += 1;
+        } else {
+            report.uploaded += 1;
+            report.bytes += file.length;
+        }
+        report.files.push(file);
+    }
+    report.files.sort_by(|a, b| a.local_path.cmp(&b.local_path));
+    transfer.finish(Ok(report))
 }
 
-//写入文本
+//写入文本,默认覆盖已有文件,返回实际写入的路径,跳过时为空
 #[tauri::command]
 pub async fn write_text_hdfs_file(
     id: i64,
     file_path: String,
     content: String,
-) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    upload_options: Option<UploadOptions>,
+) -> Result<Option<String>, String> {
+    let upload_options = upload_options.unwrap_or(UploadOptions {
+        conflict: ConflictPolicy::Overwrite,
+        ..Default::default()
+    });
+    upload_options.check()?;
+    let client = get_hdfs_client(id).await?;
 
+    let resolved = resolve_target(&client, &file_path, &upload_options, None).await?;
+    let Some((file_path, overwrite)) = resolved else {
+        return Ok(None);
+    };
+    let copying_path = format!("{}{}", &file_path, COPYING_SUFFIX);
     let mut hdfs_file_writer = client
-        .create(&file_path, WriteOptions::default().overwrite(true))
-        .await
-        .map_err(|e| e.to_string())?;
+        .create(&copying_path, upload_options.write_options(true))
+        .await?;
 
-    hdfs_file_writer
+    let written = hdfs_file_writer
         .write(bytes::Bytes::copy_from_slice(content.as_bytes()))
-        .await
-        .map_err(|e| e.to_string())?;
-    hdfs_file_writer.close().await.map_err(|e| e.to_string())?;
+        .await;
+    let closed = hdfs_file_writer.close().await;
+    if let Err(e) = written.and(closed) {
+        delete_copying(&client, &copying_path).await;
+        return Err(e);
+    }
+    rename_copying(&client, &copying_path, &file_path, overwrite).await?;
 
-    Ok(true)
+    Ok(Some(file_path))
 }
 
 //删除文件
 #[tauri::command]
 pub async fn delete_hdfs_files(id: i64, file_path_list: Vec<String>) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
     let username = get_hdfs_username(id).await.map_err(|e| e.to_string())?;
 
-    let trash_path = format!("/user/{}/.Trash/Current", &username);
-
     for file_path in file_path_list {
-        let trash_target_path = format!("{}{}", &trash_path, &file_path);
-
-        match std::path::Path::new(&trash_target_path).parent() {
-            Some(trash_target_parent_path) => {
-                client
-                    .mkdirs(
-                        trash_target_parent_path.to_str().unwrap_or_default(),
-                        0o755,
-                        true,
-                    )
-                    .await
-                    .map_err(|e| e.to_string())?;
-            }
-            None => {}
-        };
-        client
-            .rename(&file_path, &trash_target_path, true)
-            .await
-            .map_err(|e| e.to_string())?;
+        client.move_to_trash(&file_path, &username).await?;
     }
     Ok(true)
 }
@@ -252,12 +898,9 @@ pub async fn delete_hdfs_files(id: i64, file_path_list: Vec<String>) -> Result<b
 //删除文件(跳过垃圾箱)
 #[tauri::command]
 pub async fn delete_hdfs_files_force(id: i64, file_path_list: Vec<String>) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
     for file_path in file_path_list {
-        client
-            .delete(&file_path, true)
-            .await
-            .map_err(|e| e.to_string())?;
+        client.delete(&file_path, true).await?;
     }
     Ok(true)
 }
@@ -270,7 +913,7 @@ pub async fn rename_hdfs_file(
     new_file_name: String,
     overwrite: bool,
 ) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
     let old_file_parent_path = std::path::Path::new(&old_file_path)
         .parent()
         .map_or("", |v| v.to_str().unwrap_or_default())
@@ -279,8 +922,7 @@ pub async fn rename_hdfs_file(
     let new_file_path = format!("{}/{}", &old_file_parent_path, &new_file_name);
     client
         .rename(&old_file_path, &new_file_path, overwrite)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     Ok(true)
 }
@@ -292,11 +934,10 @@ pub async fn create_hdfs_dir(
     parent_path: String,
     dir_name: String,
 ) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
     client
         .mkdirs(&format!("{}/{}", &parent_path, &dir_name), 0o755, false)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
     Ok(true)
 }
 
@@ -307,14 +948,14 @@ pub async fn create_hdfs_empty_file(
     parent_path: String,
     file_name: String,
 ) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
-    client
+    let client = get_hdfs_client(id).await?;
+    let mut hdfs_file_writer = client
         .create(
             &format!("{}/{}", &parent_path, &file_name),
             WriteOptions::default(),
         )
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
+    hdfs_file_writer.close().await?;
     Ok(true)
 }
 
@@ -337,17 +978,13 @@ pub async fn get_hdfs_file_content_preview(
     id: i64,
     file_path: String,
 ) -> Result<HdfsFileContentPreview, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
-    let file_status = client
-        .get_file_info(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
-    let mut hdfs_file_reader = client.read(&file_path).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
+    let file_status = client.get_file_info(&file_path).await?;
+    let mut hdfs_file_reader = client.read(&file_path).await?;
 
     let buf: Bytes = hdfs_file_reader
         .read(1 * 1024 * 1024)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
     //判断文件是否ORC
     let content = String::from_utf8_lossy(buf.to_vec().as_slice()).to_string();
     let isorc = content.starts_with("ORC");
@@ -371,35 +1008,238 @@ pub struct HdfsFileContent {
 }
 #[tauri::command]
 pub async fn get_hdfs_file_content(id: i64, file_path: String) -> Result<HdfsFileContent, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
-    let file_status = client
-        .get_file_info(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
-    let mut hdfs_file_reader = client.read(&file_path).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
+    let file_status = client.get_file_info(&file_path).await?;
+    let mut hdfs_file_reader = client.read(&file_path).await?;
 
     let buf: Bytes = hdfs_file_reader
         .read(file_status.length as usize)
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
     Ok(HdfsFileContent {
         content: String::from_utf8_lossy(buf.to_vec().as_slice()).to_string(),
         length: file_status.length as usize,
     })
 }
 
+//下载选项
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct DownloadOptions {
+    //同时下载的文件数
+    pub concurrency: Option<usize>,
+}
+
+//单个文件的下载结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct FileDownloadResult {
+    pub source_path: String,
+    pub local_path: String,
+    pub length: u64,
+    //从.part文件续传时已有的字节数
+    pub resumed_from: u64,
+    //本地文件的大小和修改时间与hdfs相同,跳过
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+//目录下载结果
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct FolderDownloadReport {
+    pub downloaded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub bytes: u64,
+    pub files: Vec<FileDownloadResult>,
+}
+
+//下载时的.part文件和记录来源的文件
+fn partial_paths(local_path: &Path) -> (PathBuf, PathBuf) {
+    let local_path = local_path.display();
+    (
+        PathBuf::from(format!("{}{}", local_path, PARTIAL_SUFFIX)),
+        PathBuf::from(format!("{}{}", local_path, PARTIAL_SOURCE_SUFFIX)),
+    )
+}
+
+//download_folder下载hdfs_path时的本地路径,为目标目录加上hdfs路径
+fn download_local_path(target_root: &str, hdfs_path: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}{}",
+        target_root.replace("\\", "/"),
+        hdfs_path.replace("\\", "/")
+    ))
+}
+
+//删除download_folder下载source_file_path到target_file_parent_path时留下的.part文件。
+//只处理hdfs上各文件对应的本地路径,并且只删除有.part.source记录(即由下载写下)的,
+//已下载完的文件和用户自己的.part文件不受影响
+pub async fn remove_partial_files(
+    id: i64,
+    source_file_path: &str,
+    target_file_parent_path: &str,
+) -> Result<(), String> {
+    let client = get_hdfs_client(id).await?;
+    let root = client.get_file_info(source_file_path).await?;
+    let mut files = vec![];
+    if root.isdir {
+        let mut dir = client.list_status_iter(source_file_path, true);
+        while let Some(entry) = dir.next().await {
+            let entry = entry.map_err(|e| format!("entry file status :{}", &e))?;
+            if !entry.isdir {
+                files.push(download_local_path(target_file_parent_path, &entry.path));
+            }
+        }
+    } else {
+        files.push(download_local_path(target_file_parent_path, &root.path));
+    }
+    tokio::task::spawn_blocking(move || {
+        for local_path in files {
+            let (part_path, source_path) = partial_paths(&local_path);
+            if !source_path.is_file() {
+                continue;
+            }
+            for path in [part_path, source_path] {
+                match std::fs::remove_file(&path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(format!("remove {} error :{}", path.display(), e));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+//下载hdfs文件到local_path,先写入.part文件,长度一致后改名
+//失败或取消时保留.part文件,hdfs文件的长度和修改时间没变时下次从它的末尾续传
+async fn download_hdfs_file(
+    client: &HdfsClient,
+    status: &FileStatus,
+    local_path: &Path,
+    transfer: &Transfer,
+) -> Result<FileDownloadResult, String> {
+    transfer.check_cancelled()?;
+    let length = status.length as u64;
+    let mtime = std::time::UNIX_EPOCH + Duration::from_millis(status.modification_time);
+    let mut result = FileDownloadResult {
+        source_path: status.path.clone(),
+        local_path: local_path.to_string_lossy().to_string(),
+        length,
+        ..Default::default()
+    };
+
+    //已经下载过
+    let existing = tokio::fs::metadata(local_path)
+        .await
+        .ok()
+        .filter(|m| m.is_file());
+    if existing.is_some_and(|m| m.len() == length && m.modified().ok() == Some(mtime)) {
+        transfer.advance(length);
+        transfer.file_done();
+        result.skipped = true;
+        return Ok(result);
+    }
+
+    let (part_path, source_path) = partial_paths(local_path);
+    let source = format!("{} {}", length, status.modification_time);
+    let part_length = tokio::fs::metadata(&part_path)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    //.part文件来自同一个版本的hdfs文件才续传
+    let same_source = tokio::fs::read_to_string(&source_path)
+        .await
+        .is_ok_and(|recorded| recorded == source);
+    if same_source && part_length <= length {
+        result.resumed_from = part_length;
+    } else {
+        tokio::fs::write(&source_path, &source)
+            .await
+            .map_err(|e| format!("write file {} error :{}", source_path.display(), e))?;
+    }
+    let mut part_file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&part_path)
+        .await
+        .map_err(|e| format!("create file {} error :{}", part_path.display(), e))?;
+    part_file
+        .set_len(result.resumed_from)
+        .await
+        .map_err(|e| e.to_string())?;
+    part_file
+        .seek(SeekFrom::Start(result.resumed_from))
+        .await
+        .map_err(|e| e.to_string())?;
+    transfer.advance(result.resumed_from);
+
+    let hdfs_file_reader = client
+        .read(&status.path)
+        .await
+        .map_err(|e| format!("read file error :{}", &e))?;
+    let mut offset = result.resumed_from;
+    while offset < length {
+        transfer.check_cancelled()?;
+        let len = DOWNLOAD_BUFFER_SIZE.min((length - offset) as usize);
+        let buf = hdfs_file_reader.read_range(offset as usize, len).await?;
+        if buf.is_empty() {
+            return Err(format!(
+                "{} ended at {} of {} bytes",
+                &status.path, offset, length
+            ));
+        }
+        part_file.write_all(&buf).await.map_err(|e| e.to_string())?;
+        offset += buf.len() as u64;
+        transfer.advance(buf.len() as u64);
+    }
+    part_file.sync_all().await.map_err(|e| e.to_string())?;
+    drop(part_file);
+
+    let written = tokio::fs::metadata(&part_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .len();
+    if written != length {
+        return Err(format!(
+            "{} has {} bytes, expected {}",
+            part_path.display(),
+            written,
+            length
+        ));
+    }
+    //保留hdfs的修改时间,用于判断是否已经下载过
+    let set_modified = std::fs::File::options()
+        .write(true)
+        .open(&part_path)
+        .and_then(|f| f.set_modified(mtime));
+    if let Err(e) = set_modified {
+        log::warn!("set modified time of {} error :{}", part_path.display(), e);
+    }
+    tokio::fs::rename(&part_path, local_path)
+        .await
+        .map_err(|e| format!("rename {} error :{}", part_path.display(), e))?;
+    let _ = tokio::fs::remove_file(&source_path).await;
+    transfer.file_done();
+    Ok(result)
+}
+
 //下载文件到目标目录
 #[tauri::command]
 pub async fn download_file(
+    app: AppHandle,
     id: i64,
     source_file_path: String,
     target_file_parent_path: String,
-) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
-    let mut hdfs_file_reader = client
-        .read(&source_file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    transfer_id: String,
+) -> Result<FileDownloadResult, String> {
+    let client = get_hdfs_client(id).await?;
+    let status = client.get_file_info(&source_file_path).await?;
+    if status.isdir {
+        return Err(format!("{} is a directory", &source_file_path));
+    }
 
     let source_file_name = std::path::Path::new(&source_file_path)
         .file_name()
@@ -407,98 +1247,442 @@ pub async fn download_file(
         .to_str()
         .unwrap_or_default()
         .to_string();
+    let local_path = Path::new(&target_file_parent_path).join(&source_file_name);
 
-    let mut target_file = File::create(&format!(
-        "{}/{}",
-        &target_file_parent_path, &source_file_name
-    ))
-    .map_err(|e| e.to_string())?;
+    let transfer = Transfer::start(
+        app,
+        &transfer_id,
+        id,
+        &source_file_path,
+        &local_path.to_string_lossy(),
+        status.length as u64,
+        1,
+    )?;
+    let result = download_hdfs_file(&client, &status, &local_path, &transfer).await;
+    transfer.finish(result)
+}
 
-    loop {
-        if let Ok(b) = hdfs_file_reader.read(102400).await {
-            // 如果没有字节可读，跳出循环
-            if b.len() == 0 {
-                break;
+//下载hdfs目录(或文件)到目标本地目录,本地路径为目标目录加上hdfs路径
+#[tauri::command]
+pub async fn download_folder(
+    app: AppHandle,
+    id: i64,
+    source_file_path: String,
+    target_file_parent_path: String,
+    transfer_id: String,
+    options: Option<DownloadOptions>,
+) -> Result<FolderDownloadReport, String> {
+    let options = options.unwrap_or_default();
+    let client = get_hdfs_client(id).await?;
+    let target_root = target_file_parent_path.replace("\\", "/");
+    let local_path = |hdfs_path: &str| download_local_path(&target_root, hdfs_path);
+
+    //先列出全部文件,建好本地目录
+    let root = client.get_file_info(&source_file_path).await?;
+    let mut files = vec![];
+    if root.isdir {
+        std::fs::create_dir_all(local_path(&root.path))
+            .map_err(|e| format!("create folder error :{}", &e.to_string()))?;
+        let mut dir = client.list_status_iter(&source_file_path, true);
+        while let Some(entry) = dir.next().await {
+            let entry = entry.map_err(|e| format!("entry file status :{}", &e))?;
+            if entry.isdir {
+                std::fs::create_dir_all(local_path(&entry.path))
+                    .map_err(|e| format!("create folder error :{}", &e.to_string()))?;
+            } else {
+                files.push(entry);
+            }
+        }
+    } else {
+        files.push(root);
+    }
+    for file in &files {
+        let Some(parent) = local_path(&file.path).parent().map(PathBuf::from) else {
+            continue;
+        };
+        std::fs::create_dir_all(&parent)
+            .map_err(|e| format!("create folder error :{}", &e.to_string()))?;
+    }
+
+    let total = files.iter().map(|f| f.length as u64).sum();
+    let transfer = Transfer::start(
+        app,
+        &transfer_id,
+        id,
+        &source_file_path,
+        &target_root,
+        total,
+        files.len() as u64,
+    )?;
+
+    let concurrency = options.concurrency.unwrap_or(4).clamp(1, 16);
+    let files: Vec<FileDownloadResult> = futures::stream::iter(files)
+        .map(|status| {
+            let client = &client;
+            let transfer = &transfer;
+            let local_path = local_path(&status.path);
+            async move {
+                download_hdfs_file(client, &status, &local_path, transfer)
+                    .await
+                    .unwrap_or_else(|e| FileDownloadResult {
+                        source_path: status.path.clone(),
+                        local_path: local_path.to_string_lossy().to_string(),
+                        length: status.length as u64,
+                        error: Some(e),
+                        ..Default::default()
+                    })
             }
-            // 从buffer构造字符串
-            let _write_size = target_file.write(&b).map_err(|e| e.to_string())?;
-            //log::info!("writeSize:{}", writeSize);
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let mut report = FolderDownloadReport::default();
+    for file in files {
+        if file.error.is_some() {
+            report.failed += 1;
+        } else if file.skipped {
+            report.skipped += 1;
         } else {
-            break;
+            report.downloaded += 1;
+            report.bytes += file.length - file.resumed_from;
         }
+        report.files.push(file);
     }
+    report.files.sort_by_key(|f| f.source_path.clone());
+    transfer.finish(Ok(report))
+}
 
-    Ok(true)
+//hdfs内复制的选项,冲突策略同上传,skip_if_same比较大小和修改时间
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct CopyOptions {
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+    //保留源文件的权限、所有者和组、修改和访问时间、acl
+    #[serde(default)]
+    pub preserve_permission: bool,
+    #[serde(default)]
+    pub preserve_owner: bool,
+    #[serde(default)]
+    pub preserve_times: bool,
+    #[serde(default)]
+    pub preserve_acls: bool,
+    //同时复制的文件数
+    pub concurrency: Option<usize>,
+}
+
+impl CopyOptions {
+    fn preserves_any(&self) -> bool {
+        self.preserve_permission || self.preserve_owner || self.preserve_times || self.preserve_acls
+    }
+}
+
+//单个文件的复制结果,target_path为实际写入的路径
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct FileCopyResult {
+    pub source_path: String,
+    pub target_path: String,
+    pub length: u64,
+    //按冲突策略跳过
+    pub skipped: bool,
+    pub error: Option<String>,
+    //已复制,但保留属性失败
+    pub warning: Option<String>,
+}
+
+//复制结果,warnings为保留属性失败的文件和目录数,目录只在属性设置失败时出现在files中
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct CopyReport {
+    pub copied: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub warnings: usize,
+    pub bytes: u64,
+    pub files: Vec<FileCopyResult>,
+}
+
+//按选项把源文件的属性设置到目标,时间最后设置
+async fn preserve_attributes(
+    client: &HdfsClient,
+    source: &FileStatus,
+    target_path: &str,
+    options: &CopyOptions,
+) -> Result<(), String> {
+    if options.preserve_permission {
+        client
+            .set_permission(target_path, source.permission as u32)
+            .await
+            .map_err(|e| format!("set permission of {} error :{}", target_path, e))?;
+    }
+    if options.preserve_owner {
+        client
+            .set_owner(target_path, Some(&source.owner), Some(&source.group))
+            .await
+            .map_err(|e| format!("set owner of {} error :{}", target_path, e))?;
+    }
+    if options.preserve_acls {
+        let acl_status = client.get_acl_status(&source.path).await?;
+        if !acl_status.entries.is_empty() {
+            client
+                .modify_acl_entries(target_path, acl_status.entries)
+                .await
+                .map_err(|e| format!("set acl of {} error :{}", target_path, e))?;
+        }
+    }
+    if options.preserve_times {
+        client
+            .set_times(
+                target_path,
+                Some(source.modification_time),
+                Some(source.access_time),
+            )
+            .await
+            .map_err(|e| format!("set times of {} error :{}", target_path, e))?;
+    }
+    Ok(())
+}
+
+//把hdfs文件复制到target_path,返回实际写入的路径和保留属性失败的提示,按冲突策略跳过时返回None。
+//先写入临时文件,属性也设置在临时文件上,再改名为目标文件;取消或失败时只删除临时文件
+async fn copy_hdfs_file(
+    client: &HdfsClient,
+    source: &FileStatus,
+    target_path: &str,
+    options: &CopyOptions,
+    transfer: &Transfer,
+) -> Result<Option<(String, Option<String>)>, String> {
+    transfer.check_cancelled()?;
+    let length = source.length as u64;
+    let write_options = UploadOptions {
+        conflict: options.conflict,
+        ..Default::default()
+    };
+    let resolved = resolve_target(
+        client,
+        target_path,
+        &write_options,
+        Some((length, Some(source.modification_time))),
+    )
+    .await?;
+    let Some((target_path, overwrite)) = resolved else {
+        transfer.advance(length);
+        transfer.file_done();
+        return Ok(None);
+    };
+    let target_path = target_path.as_str();
+
+    let hdfs_file_reader = client
+        .read(&source.path)
+        .await
+        .map_err(|e| format!("read file error :{}", &e))?;
+    let copying_path = format!("{}{}", target_path, COPYING_SUFFIX);
+    let mut hdfs_file_writer = client
+        .create(&copying_path, write_options.write_options(true))
+        .await?;
+
+    let copied =
+        copy_hdfs_file_data(&hdfs_file_reader, &mut hdfs_file_writer, source, transfer).await;
+    let copied = match copied {
+        Ok(()) => hdfs_file_writer.close().await,
+        Err(e) => {
+            //先关闭再删除,否则文件租约要等到超时才释放
+            if let Err(close_error) = hdfs_file_writer.close().await {
+                log::warn!("close {} error :{}", &copying_path, close_error);
+            }
+            Err(e)
+        }
+    };
+    if let Err(e) = copied {
+        delete_copying(client, &copying_path).await;
+        return Err(e);
+    }
+    //改名不改变权限、所有者、acl和时间
+    let warning = preserve_attributes(client, source, &copying_path, options)
+        .await
+        .err()
+        .map(|e| e.replace(&copying_path, target_path));
+    //保留修改时间,下次复制时才能比较
+    if options.conflict == ConflictPolicy::SkipIfSame && !options.preserve_times {
+        let set_times = client
+            .set_times(&copying_path, Some(source.modification_time), None)
+            .await;
+        if let Err(e) = set_times {
+            log::warn!("set times of {} error :{}", &copying_path, e);
+        }
+    }
+    rename_copying(client, &copying_path, target_path, overwrite).await?;
+    transfer.file_done();
+    Ok(Some((target_path.to_string(), warning)))
+}
+
+//按UPLOAD_BUFFER_SIZE分块从源文件读取并写入目标,每块之间检查是否取消
+async fn copy_hdfs_file_data(
+    hdfs_file_reader: &HdfsFileReader,
+    hdfs_file_writer: &mut HdfsFileWriter,
+    source: &FileStatus,
+    transfer: &Transfer,
+) -> Result<(), String> {
+    let length = source.length as u64;
+    let mut offset = 0;
+    while offset < length {
+        transfer.check_cancelled()?;
+        let len = UPLOAD_BUFFER_SIZE.min((length - offset) as usize);
+        let buf = hdfs_file_reader.read_range(offset as usize, len).await?;
+        if buf.is_empty() {
+            return Err(format!(
+                "{} ended at {} of {} bytes",
+                &source.path, offset, length
+            ));
+        }
+        let len = buf.len() as u64;
+        hdfs_file_writer.write(buf).await?;
+        offset += len;
+        transfer.advance(len);
+    }
+    Ok(())
 }
 
-//下载hdfs目录到目标本地目录
+//在hdfs内把文件或目录复制到target_parent_path下,目录递归复制,数据经客户端中转
+//目标目录已存在时合并,文件按冲突策略处理;复制到源所在目录时只能用rename或skip
 #[tauri::command]
-pub async fn download_folder(
+pub async fn copy_hdfs_files(
+    app: AppHandle,
     id: i64,
-    source_file_path: String,
-    target_file_parent_path: String,
-) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
-
-    let dir = client.list_status_iter(&source_file_path, true);
-    while let Some(entry) = dir.next().await {
-        let entry = entry.map_err(|e| format!("entry file status :{}", &e.to_string()))?;
-        let entry_path = entry.path.replace("\\", "/");
-
-        std::fs::create_dir_all(&target_file_parent_path.replace("\\", "/"))
-            .map_err(|e| e.to_string())?;
-
-        if entry.isdir {
-            //目录创建目录
-            let target_dir_path = format!(
-                "{}{}",
-                &target_file_parent_path.replace("\\", "/"),
-                &entry_path
-            );
-            std::fs::create_dir_all(&target_dir_path.replace("\\", "/"))
-                .map_err(|e| format!("create folder error :{}", &e.to_string()))?;
+    source_paths: Vec<String>,
+    target_parent_path: String,
+    transfer_id: String,
+    options: Option<CopyOptions>,
+) -> Result<CopyReport, String> {
+    let options = options.unwrap_or_default();
+    let client = get_hdfs_client(id).await?;
+    let target_parent = target_parent_path.trim_end_matches('/');
+    let mut report = CopyReport::default();
+
+    //先列出全部目录和文件,及其目标路径
+    let mut dirs = vec![];
+    let mut files = vec![];
+    for source_path in &source_paths {
+        let source_path = source_path.trim_end_matches('/');
+        let name = source_path.rsplit('/').next().unwrap_or_default();
+        if name.is_empty() {
+            return Err("Cannot copy the root directory".to_string());
+        }
+        let source = client.get_file_info(source_path).await?;
+        if source.isdir
+            && (target_parent == source_path
+                || target_parent.starts_with(&format!("{}/", source_path)))
+        {
+            return Err(format!("Cannot copy {} into itself", source_path));
+        }
+        let mut target_path = format!("{}/{}", target_parent, name);
+        if target_path == source_path {
+            match options.conflict {
+                ConflictPolicy::Rename => target_path = free_path(&client, &target_path).await?,
+                ConflictPolicy::Skip => {
+                    report.skipped += 1;
+                    report.files.push(FileCopyResult {
+                        source_path: source_path.to_string(),
+                        target_path,
+                        length: source.length as u64,
+                        skipped: true,
+                        ..Default::default()
+                    });
+                    continue;
+                }
+                _ => return Err(format!("{} is the same as the source", target_path)),
+            }
+        }
+        if !source.isdir {
+            files.push((source, target_path));
             continue;
-        } else {
-            let target_file_path = format!(
-                "{}{}",
-                &target_file_parent_path.replace("\\", "/"),
-                &entry_path
-            );
-            let target_file_dir_path = Path::new(&target_file_path).parent();
-            if !target_file_dir_path.is_none() {
-                std::fs::create_dir_all(&target_file_dir_path.unwrap())
-                    .map_err(|e| format!("create folder error :{}", &e.to_string()))?;
+        }
+        let mut dir = client.list_status_iter(source_path, true);
+        while let Some(entry) = dir.next().await {
+            let entry = entry.map_err(|e| format!("entry file status :{}", &e))?;
+            let relative_path = entry.path.strip_prefix(source_path).unwrap_or_default();
+            let entry_target_path = format!("{}{}", &target_path, relative_path);
+            if entry.isdir {
+                dirs.push((entry, entry_target_path));
+            } else {
+                files.push((entry, entry_target_path));
             }
+        }
+        dirs.insert(0, (source, target_path));
+    }
+    //空目录也保留,属性在文件复制完后设置
+    for (_, target_path) in &dirs {
+        client.mkdirs(target_path, 0o755, true).await?;
+    }
 
-            let mut target_file = File::create(&target_file_path).map_err(|e| {
-                format!(
-                    "create file {}{} error :{}",
-                    &target_file_parent_path.replace("\\", "/"),
-                    &entry_path,
-                    &e.to_string()
-                )
-            })?;
-            let mut hdfs_file_reader = client
-                .read(&entry_path)
-                .await
-                .map_err(|e| format!("read file error :{}", &e.to_string()))?;
+    let total = files.iter().map(|(f, _)| f.length as u64).sum();
+    let transfer = Transfer::start(
+        app,
+        &transfer_id,
+        id,
+        &source_paths.join(", "),
+        target_parent,
+        total,
+        files.len() as u64,
+    )?;
 
-            loop {
-                if let Ok(b) = hdfs_file_reader.read(102400).await {
-                    // 如果没有字节可读，跳出循环
-                    if b.len() == 0 {
-                        break;
+    let concurrency = options.concurrency.unwrap_or(4).clamp(1, 16);
+    let results: Vec<FileCopyResult> = futures::stream::iter(files)
+        .map(|(status, target_path)| {
+            let client = &client;
+            let options = &options;
+            let transfer = &transfer;
+            async move {
+                let mut result = FileCopyResult {
+                    source_path: status.path.clone(),
+                    target_path: target_path.clone(),
+                    length: status.length as u64,
+                    ..Default::default()
+                };
+                match copy_hdfs_file(client, &status, &target_path, options, transfer).await {
+                    Ok(Some((written_path, warning))) => {
+                        result.target_path = written_path;
+                        result.warning = warning;
                     }
-                    // 从buffer构造字符串
-                    let _write_size = target_file.write(&b).map_err(|e| e.to_string())?;
-                    //log::info!("writeSize:{}", writeSize);
-                } else {
-                    break;
+                    Ok(None) => result.skipped = true,
+                    Err(e) => result.error = Some(e),
                 }
+                result
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    for file in results {
+        if file.error.is_some() {
+            report.failed += 1;
+        } else if file.skipped {
+            report.skipped += 1;
+        } else {
+            report.copied += 1;
+            report.bytes += file.length;
+            if file.warning.is_some() {
+                report.warnings += 1;
             }
         }
+        report.files.push(file);
     }
-    Ok(true)
+    //子目录先于父目录设置,目录的修改时间不会再被写入改变
+    if options.preserves_any() && !transfer.is_cancelled() {
+        for (status, target_path) in dirs.iter().rev() {
+            if let Err(e) = preserve_attributes(&client, status, target_path, &options).await {
+                report.warnings += 1;
+                report.files.push(FileCopyResult {
+                    source_path: status.path.clone(),
+                    target_path: target_path.clone(),
+                    warning: Some(e),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    report.files.sort_by_key(|f| f.source_path.clone());
+    transfer.finish(Ok(report))
 }
 
 //设置权限
@@ -509,27 +1693,24 @@ pub async fn set_hdfs_files_permissions(
     permission: u32,
     recursive: bool,
 ) -> Result<bool, String> {
-    let client = get_hdfs_client(id).await.map_err(|e| e.to_string())?;
+    let client = get_hdfs_client(id).await?;
 
     return set_files_permission_impl(&client, file_path_list, permission, recursive).await;
 }
 
 pub fn set_files_permission_impl(
-    client: &Client,
+    client: &HdfsClient,
     file_path_list: Vec<String>,
     permission: u32,
     recursive: bool,
 ) -> BoxFuture<Result<bool, String>> {
     async move {
         for file_path in file_path_list {
-            client
-                .set_permission(&file_path, permission)
-                .await
-                .map_err(|e| e.to_string())?;
+            client.set_permission(&file_path, permission).await?;
             if recursive {
-                let dir = client.list_status_iter(&file_path, recursive);
+                let mut dir = client.list_status_iter(&file_path, recursive);
                 while let Some(entry) = dir.next().await {
-                    let entry = entry.map_err(|e| e.to_string())?;
+                    let entry = entry?;
                     let entry_path = entry.path.replace("\\", "/");
                     //log::info!("entry_path:{}", &entry_path);
                     set_files_permission_impl(client, vec![entry_path], permission, false).await?;
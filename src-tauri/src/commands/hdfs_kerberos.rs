@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::hdfs_config::{KERBEROS_PASSWORD_REQUIRED, init_connection};
+use super::hdfs_connection::is_connected;
+use crate::kerberos::ccache;
+use crate::kerberos::enctype::enctype_name;
+use crate::kerberos::keytab::{self, KeytabEntry};
+
+//票据状态变化时发给前端的事件
+pub const KERBEROS_TICKET_EVENT: &str = "kerberos-ticket-status";
+//后台检查票据的间隔
+const TICKET_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+//票据到期前多少秒重新登录
+const RENEW_BEFORE_SECS: i64 = 10 * 60;
+
+//票据状态
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketState {
+    #[default]
+    Valid,
+    //刚重新登录
+    Renewed,
+    //即将到期
+    Expiring,
+    Expired,
+    //没有keytab也没有保存密码,需要用户重新登录
+    LoginRequired,
+    //票据缓存无法读取
+    Error,
+}
+
+//连接的kerberos票据状态,时间为unix秒
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct KerberosTicketStatus {
+    pub id: i64,
+    pub principal: String,
+    pub end_time: i64,
+    pub renew_till: i64,
+    pub expires_in_secs: i64,
+    pub state: TicketState,
+    pub message: String,
+}
+
+//最近一次检查的票据状态
+static TICKET_STATUS: Lazy<Mutex<HashMap<i64, KerberosTicketStatus>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+//从连接的票据缓存读取TGT的到期时间
+fn read_ticket_status(id: i64) -> Result<KerberosTicketStatus, String> {
+    let path = ccache::ccache_path(id);
+    let cache = ccache::read_ccache(&path)?;
+    let tgt = cache
+        .tgt()
+        .ok_or_else(|| format!("No TGT in {}", path.display()))?;
+    let expires_in_secs = tgt.end_time - chrono::Utc::now().timestamp();
+    let state = if expires_in_secs <= 0 {
+        TicketState::Expired
+    } else if expires_in_secs <= RENEW_BEFORE_SECS {
+        TicketState::Expiring
+    } else {
+        TicketState::Valid
+    };
+    Ok(KerberosTicketStatus {
+        id,
+        principal: cache.default_principal.to_string(),
+        end_time: tgt.end_time,
+        renew_till: tgt.renew_till,
+        expires_in_secs,
+        state,
+        message: String::new(),
+    })
+}
+
+//检查单个连接的票据,已打开的连接快到期时用keytab或已保存的密码重新登录。
+//kerbeiros只支持AS请求,可续期票据也是重新申请而不是TGS续期
+async fn check_ticket(id: i64) -> KerberosTicketStatus {
+    let status = match read_ticket_status(id) {
+        Ok(status) => status,
+        Err(e) => {
+            return KerberosTicketStatus {
+                id,
+                state: TicketState::Error,
+                message: e,
+                ..Default::default()
+            };
+        }
+    };
+    if status.state == TicketState::Valid || !is_connected(id) {
+        return status;
+    }
+
+    //需要密码且票据没有变化时不再重复尝试
+    let previous = TICKET_STATUS
+        .lock()
+        .ok()
+        .and_then(|s| s.get(&id).cloned())
+        .filter(|p| p.state == TicketState::LoginRequired && p.end_time == status.end_time);
+    if let Some(previous) = previous {
+        return KerberosTicketStatus {
+            expires_in_secs: status.expires_in_secs,
+            ..previous
+        };
+    }
+
+    log::info!(
+        "Kerberos ticket of connection {} expires in {}s, logging in again",
+        id,
+        status.expires_in_secs
+    );
+    match init_connection(id, None, None).await {
+        Ok(()) => match read_ticket_status(id) {
+            Ok(renewed) => KerberosTicketStatus {
+                state: TicketState::Renewed,
+                ..renewed
+            },
+            Err(e) => KerberosTicketStatus {
+                state: TicketState::Error,
+                message: e,
+                ..status
+            },
+        },
+        Err(e) if e.starts_with(KERBEROS_PASSWORD_REQUIRED) => KerberosTicketStatus {
+            state: TicketState::LoginRequired,
+            message: e,
+            ..status
+        },
+        Err(e) => {
+            log::warn!("Kerberos renewal of connection {} failed: {}", id, e);
+            KerberosTicketStatus {
+                message: e,
+                ..status
+            }
+        }
+    }
+}
+
+//启动后台票据检查任务
+pub fn spawn_ticket_renewal(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            for id in ccache::cached_connection_ids() {
+                let status = check_ticket(id).await;
+                if let Err(e) = app.emit(KERBEROS_TICKET_EVENT, &status) {
+                    log::warn!("Failed to emit {}: {}", KERBEROS_TICKET_EVENT, e);
+                }
+                if let Ok(mut ticket_status) = TICKET_STATUS.lock() {
+                    ticket_status.insert(id, status);
+                }
+            }
+            tokio::time::sleep(TICKET_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+//获取连接当前的票据状态,没有票据缓存时返回空
+#[tauri::command]
+pub async fn get_kerberos_ticket_status(id: i64) -> Result<Option<KerberosTicketStatus>, String> {
+    if !ccache::ccache_path(id).is_file() {
+        return Ok(None);
+    }
+    read_ticket_status(id).map(Some)
+}
+
+//票据缓存中的一张票据,时间为unix秒
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct KerberosTicket {
+    pub client: String,
+    pub server: String,
+    pub enctype: i32,
+    pub enctype_name: String,
+    //klist -f风格的标志字母
+    pub flags: String,
+    pub flag_names: Vec<String>,
+    pub auth_time: i64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub renew_till: i64,
+    pub expired: bool,
+}
+
+//klist风格的票据列表
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct KerberosTicketList {
+    //票据缓存文件
+    pub ccache: String,
+    //是否为该连接自己的票据缓存,否则来自KRB5CCNAME
+    pub connection_ccache: bool,
+    pub version: u16,
+    pub default_principal: String,
+    pub tickets: Vec<KerberosTicket>,
+}
+
+//列出连接正在使用的票据缓存中的票据
+#[tauri::command]
+pub async fn get_kerberos_tickets(id: i64) -> Result<KerberosTicketList, String> {
+    let connection_ccache = ccache::ccache_path(id).is_file();
+    let path = ccache::resolve_ccache_name(ccache::connection_ccache(id).as_ref())?;
+    let cache = ccache::read_ccache(&path)?;
+    let now = chrono::Utc::now().timestamp();
+    let tickets = cache
+        .credentials
+        .iter()
+        .filter(|c| !c.is_config_entry())
+        .map(|c| KerberosTicket {
+            client: c.client.to_string(),
+            server: c.server.to_string(),
+            enctype: c.enctype as i32,
+            enctype_name: enctype_name(c.enctype as i32),
+            flags: ccache::flag_letters(c.flags),
+            flag_names: ccache::flag_names(c.flags),
+            auth_time: c.auth_time,
+            start_time: c.start_time,
+            end_time: c.end_time,
+            renew_till: c.renew_till,
+            expired: c.end_time <= now,
+        })
+        .collect();
+    Ok(KerberosTicketList {
+        ccache: path.display().to_string(),
+        connection_ccache,
+        version: cache.version,
+        default_principal: cache.default_principal.to_string(),
+        tickets,
+    })
+}
+
+//keytab内容
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct KeytabInfo {
+    //全部条目
+    pub entries: Vec<KeytabEntry>,
+    //每个principal登录时使用的条目(kvno最大、加密最强)
+    pub principals: Vec<KeytabEntry>,
+}
+
+//解析keytab,列出全部条目和可选的principal
+#[tauri::command]
+pub async fn inspect_keytab(keytab_path: String) -> Result<KeytabInfo, String> {
+    let entries = keytab::list_keytab(&keytab_path)?;
+    let principals = keytab::keytab_principals(&entries);
+    Ok(KeytabInfo {
+        entries,
+        principals,
+    })
+}
@@ -1,8 +1,8 @@
+use crate::backend::HdfsFileReader;
 use crate::get_hdfs_client;
 use bytes::Bytes;
 use futures::StreamExt;
 use futures::TryFutureExt;
-use hdfs_native::file::FileReader;
 use orc_rust::{
     reader::{
         metadata::{read_metadata_async, FileMetadata},
@@ -23,7 +23,7 @@ pub struct OrcField {
     pub type_name: String,
 }
 
-pub struct HdfsOrcFileReader(FileReader);
+pub struct HdfsOrcFileReader(HdfsFileReader);
 impl AsyncChunkReader for HdfsOrcFileReader {
     fn len(&mut self) -> futures::future::BoxFuture<'_, std::io::Result<u64>> {
         Box::pin(futures::future::ready(Ok(self.0.file_length() as u64)))
@@ -54,10 +54,7 @@ pub async fn get_orc_reader(
     batch_size: usize,
 ) -> Result<ArrowStreamReader<HdfsOrcFileReader>, String> {
     let hdfs_client = get_hdfs_client(id).await?;
-    let hdfs_file_reader = hdfs_client
-        .read(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let hdfs_file_reader = hdfs_client.read(&file_path).await?;
     let arrow_reader: ArrowStreamReader<HdfsOrcFileReader> =
         ArrowReaderBuilder::try_new_async(HdfsOrcFileReader(hdfs_file_reader))
             .await
@@ -92,10 +89,7 @@ pub async fn get_hdfs_orc_file_field_list(
 #[tauri::command]
 pub async fn get_hdfs_orc_file_rows_count(id: i64, file_path: String) -> Result<u64, String> {
     let hdfs_client = get_hdfs_client(id).await?;
-    let hdfs_file_reader = hdfs_client
-        .read(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let hdfs_file_reader = hdfs_client.read(&file_path).await?;
     let mut orc_reader = HdfsOrcFileReader(hdfs_file_reader);
 
     let file_meta: FileMetadata = read_metadata_async::<HdfsOrcFileReader>(&mut orc_reader)
@@ -115,10 +109,7 @@ pub struct OrcMeta {
 #[tauri::command]
 pub async fn get_hdfs_orc_file_meta(id: i64, file_path: String) -> Result<OrcMeta, String> {
     let hdfs_client = get_hdfs_client(id).await?;
-    let hdfs_file_reader = hdfs_client
-        .read(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let hdfs_file_reader = hdfs_client.read(&file_path).await?;
     let mut orc_reader = HdfsOrcFileReader(hdfs_file_reader);
 
     let file_meta: FileMetadata = read_metadata_async::<HdfsOrcFileReader>(&mut orc_reader)
@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
+use crate::backend::HdfsFileReader;
 use crate::get_hdfs_client;
 use bytes::Bytes;
 use futures::StreamExt;
-use hdfs_native::file::FileReader;
 use parquet::arrow::async_reader::AsyncFileReader;
 use parquet::arrow::async_reader::MetadataFetch;
 use parquet::arrow::async_reader::ParquetRecordBatchStream;
@@ -24,7 +24,7 @@ pub struct ParquetField {
     pub type_name: String,
 }
 
-pub struct HdfsParquetFileReader(FileReader);
+pub struct HdfsParquetFileReader(HdfsFileReader);
 
 impl MetadataFetch for HdfsParquetFileReader {
     fn fetch(
@@ -80,10 +80,7 @@ pub async fn get_parquet_reader(
     batch_size: usize,
 ) -> Result<ParquetRecordBatchStream<HdfsParquetFileReader>, String> {
     let hdfs_client = get_hdfs_client(id).await?;
-    let hdfs_file_reader = hdfs_client
-        .read(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let hdfs_file_reader = hdfs_client.read(&file_path).await?;
 
     ParquetRecordBatchStreamBuilder::new(HdfsParquetFileReader(hdfs_file_reader))
         .await
@@ -118,10 +115,7 @@ pub async fn get_hdfs_parquet_file_field_list(
 #[tauri::command]
 pub async fn get_hdfs_parquet_file_rows_count(id: i64, file_path: String) -> Result<i64, String> {
     let hdfs_client = get_hdfs_client(id).await?;
-    let hdfs_file_reader = hdfs_client
-        .read(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let hdfs_file_reader = hdfs_client.read(&file_path).await?;
     let mut parquet_reader = HdfsParquetFileReader(hdfs_file_reader);
 
     let meta = parquet_reader
@@ -143,10 +137,7 @@ pub struct ParquetMeta {
 #[tauri::command]
 pub async fn get_hdfs_parquet_file_meta(id: i64, file_path: String) -> Result<ParquetMeta, String> {
     let hdfs_client = get_hdfs_client(id).await?;
-    let hdfs_file_reader = hdfs_client
-        .read(&file_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let hdfs_file_reader = hdfs_client.read(&file_path).await?;
     let mut parquet_reader = HdfsParquetFileReader(hdfs_file_reader);
 
     let meta = parquet_reader
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+//传输进度事件
+pub const TRANSFER_PROGRESS_EVENT: &str = "hdfs-transfer-progress";
+//取消的传输返回的错误前缀
+pub const TRANSFER_CANCELLED: &str = "Transfer cancelled";
+//两次进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+//传输状态
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferState {
+    #[default]
+    Running,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+//传输进度,rate为字节/秒
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct TransferProgress {
+    pub transfer_id: String,
+    pub id: i64,
+    pub source: String,
+    pub target: String,
+    pub bytes: u64,
+    pub total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub rate: f64,
+    pub eta_secs: Option<u64>,
+    pub state: TransferState,
+    pub message: String,
+}
+
+//进度和上次发送进度的时间
+type SharedProgress = Arc<Mutex<(TransferProgress, Instant)>>;
+//进行中的传输的取消标记和进度
+type RunningTransfer = (Arc<AtomicBool>, SharedProgress);
+
+static TRANSFERS: Lazy<Mutex<HashMap<String, RunningTransfer>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+//需要保留结束时进度的传输,结束前为None
+static FINAL_PROGRESS: Lazy<Mutex<HashMap<String, Option<TransferProgress>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+//一次传输,可以被多个并发的文件共享,结束(drop)时从进行中的传输里移除
+pub struct Transfer {
+    app: AppHandle,
+    transfer_id: String,
+    cancelled: Arc<AtomicBool>,
+    started: Instant,
+    progress: SharedProgress,
+}
+
+impl Transfer {
+    pub fn start(
+        app: AppHandle,
+        transfer_id: &str,
+        id: i64,
+        source: &str,
+        target: &str,
+        total: u64,
+        files_total: u64,
+    ) -> Result<Transfer, String> {
+        if transfer_id.is_empty() {
+            return Err("transfer_id is required".to_string());
+        }
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let now = Instant::now();
+        let progress = TransferProgress {
+            transfer_id: transfer_id.to_string(),
+            id,
+            source: source.to_string(),
+            target: target.to_string(),
+            total,
+            files_total,
+            ..Default::default()
+        };
+        let progress = Arc::new(Mutex::new((progress, now)));
+        {
+            let mut transfers = TRANSFERS.lock().map_err(|e| e.to_string())?;
+            if transfers.contains_key(transfer_id) {
+                return Err(format!("Transfer {} is already running", transfer_id));
+            }
+            transfers.insert(
+                transfer_id.to_string(),
+                (cancelled.clone(), progress.clone()),
+            );
+        }
+        let transfer = Transfer {
+            app,
+            transfer_id: transfer_id.to_string(),
+            cancelled,
+            started: now,
+            progress,
+        };
+        transfer.update(true, |_| {});
+        Ok(transfer)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    //已取消时返回错误,在每块数据之间检查
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(format!("{}: {}", TRANSFER_CANCELLED, self.transfer_id));
+        }
+        Ok(())
+    }
+
+    //增加已传输的字节数,按间隔发送进度
+    pub fn advance(&self, bytes: u64) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        self.update(false, |progress| {
+            progress.bytes += bytes;
+            if elapsed > 0.0 {
+                progress.rate = progress.bytes as f64 / elapsed;
+            }
+            progress.eta_secs = (progress.rate > 0.0).then(|| {
+                (progress.total.saturating_sub(progress.bytes) as f64 / progress.rate).ceil() as u64
+            });
+        });
+    }
+
+    //一个文件传输完成
+    pub fn file_done(&self) {
+        self.update(false, |progress| progress.files_done += 1);
+    }
+
+    //发送最终状态并返回传输结果,结果为Ok但已取消时状态为cancelled
+    pub fn finish<T>(self, result: Result<T, String>) -> Result<T, String> {
+        let cancelled = self.is_cancelled();
+        self.update(true, |progress| match &result {
+            Ok(_) if cancelled => progress.state = TransferState::Cancelled,
+            Ok(_) => {
+                progress.state = TransferState::Done;
+                progress.eta_secs = Some(0);
+            }
+            Err(e) if e.starts_with(TRANSFER_CANCELLED) => {
+                progress.state = TransferState::Cancelled;
+                progress.message = e.clone();
+            }
+            Err(e) => {
+                progress.state = TransferState::Failed;
+                progress.message = e.clone();
+            }
+        });
+        result
+    }
+
+    //修改进度,force或距上次发送超过PROGRESS_INTERVAL时发送
+    fn update(&self, force: bool, f: impl FnOnce(&mut TransferProgress)) {
+        let Ok(mut guard) = self.progress.lock() else {
+            return;
+        };
+        let (progress, last_emit) = &mut *guard;
+        f(progress);
+        if !force && last_emit.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        *last_emit = Instant::now();
+        if let Err(e) = self.app.emit(TRANSFER_PROGRESS_EVENT, &*progress) {
+            log::warn!("Failed to emit {}: {}", TRANSFER_PROGRESS_EVENT, e);
+        }
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        if let Ok(mut transfers) = TRANSFERS.lock() {
+            transfers.remove(&self.transfer_id);
+        }
+        let progress = self.progress.lock().ok().map(|p| p.0.clone());
+        if let Ok(mut finals) = FINAL_PROGRESS.lock() {
+            finals
+                .entry(self.transfer_id.clone())
+                .and_modify(|slot| *slot = progress);
+        }
+    }
+}
+
+//进行中的传输的当前进度
+pub fn transfer_progress(transfer_id: &str) -> Option<TransferProgress> {
+    let transfers = TRANSFERS.lock().ok()?;
+    let (_, progress) = transfers.get(transfer_id)?;
+    let progress = progress.lock().ok()?;
+    Some(progress.0.clone())
+}
+
+//传输结束后保留它的最终进度,在开始传输前调用
+pub fn keep_final_progress(transfer_id: &str) {
+    if let Ok(mut finals) = FINAL_PROGRESS.lock() {
+        finals.insert(transfer_id.to_string(), None);
+    }
+}
+
+//取出保留的最终进度,传输没有开始时为空
+pub fn take_final_progress(transfer_id: &str) -> Option<TransferProgress> {
+    FINAL_PROGRESS.lock().ok()?.remove(transfer_id).flatten()
+}
+
+//取消传输,传输不存在(已结束)时返回false
+#[tauri::command]
+pub async fn cancel_transfer(transfer_id: String) -> Result<bool, String> {
+    let transfers = TRANSFERS.lock().map_err(|e| e.to_string())?;
+    match transfers.get(&transfer_id) {
+        Some((cancelled, _)) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,691 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+use super::hdfs_file::{
+    DownloadOptions, FolderUploadOptions, UploadOptions, download_folder, remove_partial_files,
+    upload_hdfs_file, upload_hdfs_folder,
+};
+use super::hdfs_transfer::{
+    TRANSFER_CANCELLED, TransferProgress, cancel_transfer, keep_final_progress,
+    take_final_progress, transfer_progress,
+};
+use crate::db::db_init::DB_POOL;
+
+//任务变化时发给前端的事件
+pub const TRANSFER_JOB_EVENT: &str = "hdfs-transfer-job";
+//app_setting中同时运行的任务数
+const CONCURRENCY_SETTING: &str = "transfer_queue.concurrency";
+const DEFAULT_CONCURRENCY: usize = 2;
+const MAX_CONCURRENCY: usize = 8;
+//失败后自动重试,包括第一次最多运行的次数
+const MAX_ATTEMPTS: i64 = 5;
+//重试前等待的秒数,每次翻倍
+const RETRY_BASE_SECS: i64 = 5;
+const RETRY_MAX_SECS: i64 = 300;
+//重试也不会成功的错误:目标已存在(冲突策略为Fail)、没有权限、本地文件不存在
+const PERMANENT_ERRORS: [&str; 4] = [
+    "filealreadyexistsexception",
+    "accesscontrolexception",
+    "permission denied",
+    "(os error 2)",
+];
+//没有新任务时检查到期重试的间隔
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
+//运行中的任务保存进度的间隔
+const SAVE_PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+//任务类型
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum TransferKind {
+    //上传本地文件到target目录
+    UploadFile,
+    //上传本地目录到target目录
+    UploadFolder,
+    //下载hdfs文件或目录到本地target目录
+    Download,
+}
+
+//任务状态
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum JobState {
+    #[default]
+    Queued,
+    Running,
+    Paused,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+//任务选项,按任务类型使用其中一部分
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct TransferJobOptions {
+    pub upload: Option<UploadOptions>,
+    pub folder: Option<FolderUploadOptions>,
+    pub download: Option<DownloadOptions>,
+}
+
+//传输队列中的任务,时间为unix秒
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow, Clone)]
+pub struct TransferJob {
+    pub id: i64,
+    pub hdfs_config_id: i64,
+    pub kind: TransferKind,
+    pub source: String,
+    pub target: String,
+    pub options: Json<TransferJobOptions>,
+    pub state: JobState,
+    pub bytes: i64,
+    pub total: i64,
+    pub files_done: i64,
+    pub files_total: i64,
+    //已运行的次数
+    pub attempts: i64,
+    //排队等待重试时,最早开始的时间
+    pub next_attempt_at: i64,
+    //上传目录时已完成的相对路径,续传时跳过
+    pub done_files: Json<Vec<String>>,
+    pub message: String,
+    pub created_at: String,
+    pub updated_at: String,
+    //进度事件中的transfer_id
+    #[sqlx(skip)]
+    pub transfer_id: String,
+    //运行中的速度,字节/秒
+    #[sqlx(skip)]
+    pub rate: f64,
+    #[sqlx(skip)]
+    pub eta_secs: Option<u64>,
+}
+
+impl TransferJob {
+    fn apply_progress(&mut self, progress: &TransferProgress) {
+        self.bytes = progress.bytes as i64;
+        self.total = progress.total as i64;
+        self.files_done = progress.files_done as i64;
+        self.files_total = progress.files_total as i64;
+        self.rate = progress.rate;
+        self.eta_secs = progress.eta_secs;
+    }
+}
+
+//一次运行的结果
+#[derive(Debug, Default)]
+struct JobOutcome {
+    //失败的文件数和第一个失败的错误
+    failed: usize,
+    error: String,
+    //本次完成的文件(上传目录时为相对路径)
+    done_files: Vec<String>,
+    message: String,
+}
+
+//运行中的任务,值为暂停或取消后要设置的状态
+static RUNNING_JOBS: Lazy<Mutex<HashMap<i64, Option<JobState>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+//有新任务或任务结束时唤醒调度
+static QUEUE_NOTIFY: Lazy<Notify> = Lazy::new(Notify::new);
+
+fn job_transfer_id(job_id: i64) -> String {
+    format!("job-{}", job_id)
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+async fn pool() -> Result<&'static Pool<Sqlite>, String> {
+    crate::db::db_init::init_db()
+        .await
+        .map_err(|e| e.to_string())?;
+    DB_POOL
+        .get()
+        .ok_or_else(|| "Database connection pool is not initialized".to_owned())
+}
+
+async fn get_job(job_id: i64) -> Result<TransferJob, String> {
+    let job = sqlx::query_as::<_, TransferJob>("select * from transfer_job where id = ?")
+        .bind(job_id)
+        .fetch_optional(pool().await?)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Transfer job {} not found", job_id))?;
+    Ok(TransferJob {
+        transfer_id: job_transfer_id(job.id),
+        ..job
+    })
+}
+
+async fn save_job(job: &TransferJob) -> Result<(), String> {
+    sqlx::query(
+        "update transfer_job set state = ?, bytes = ?, total = ?, files_done = ?, files_total = ?, attempts = ?, next_attempt_at = ?, done_files = ?, message = ?, updated_at = ? where id = ?",
+    )
+    .bind(job.state)
+    .bind(job.bytes)
+    .bind(job.total)
+    .bind(job.files_done)
+    .bind(job.files_total)
+    .bind(job.attempts)
+    .bind(job.next_attempt_at)
+    .bind(&job.done_files)
+    .bind(&job.message)
+    .bind(chrono::Local::now().to_rfc3339())
+    .bind(job.id)
+    .execute(pool().await?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+//状态为from之一时修改状态,返回是否修改;重新排队时重置重试次数
+async fn change_state(job_id: i64, from: &[JobState], to: JobState) -> Result<bool, String> {
+    let mut changed = false;
+    for state in from {
+        let result = sqlx::query(
+            "update transfer_job set state = ?, attempts = case when ? then 0 else attempts end, next_attempt_at = 0, message = '', updated_at = ? where id = ? and state = ?",
+        )
+        .bind(to)
+        .bind(to == JobState::Queued)
+        .bind(chrono::Local::now().to_rfc3339())
+        .bind(job_id)
+        .bind(state)
+        .execute(pool().await?)
+        .await
+        .map_err(|e| e.to_string())?;
+        changed |= result.rows_affected() > 0;
+    }
+    Ok(changed)
+}
+
+fn emit_job(app: &AppHandle, job: &TransferJob) {
+    if let Err(e) = app.emit(TRANSFER_JOB_EVENT, job) {
+        log::warn!("Failed to emit {}: {}", TRANSFER_JOB_EVENT, e);
+    }
+}
+
+async fn emit_job_by_id(app: &AppHandle, job_id: i64) {
+    match get_job(job_id).await {
+        Ok(job) => emit_job(app, &job),
+        Err(e) => log::warn!("Failed to read transfer job {}: {}", job_id, e),
+    }
+}
+
+async fn concurrency() -> Result<usize, String> {
+    let value = sqlx::query_scalar::<_, String>("select value from app_setting where key = ?")
+        .bind(CONCURRENCY_SETTING)
+        .fetch_optional(pool().await?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(value
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY))
+}
+
+fn is_permanent_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    PERMANENT_ERRORS.iter().any(|e| error.contains(e))
+}
+
+//取消下载任务时删除该任务留下的.part文件,暂停时保留以便续传
+async fn remove_partial_downloads(job: &TransferJob) {
+    if job.kind != TransferKind::Download {
+        return;
+    }
+    let removed = remove_partial_files(job.hdfs_config_id, &job.source, &job.target).await;
+    if let Err(e) = removed {
+        log::warn!(
+            "Failed to remove partial files of transfer job {}: {}",
+            job.id,
+            e
+        );
+    }
+}
+
+fn running_count() -> usize {
+    RUNNING_JOBS.lock().map(|jobs| jobs.len()).unwrap_or(0)
+}
+
+//本地路径相对于root的路径,用/分隔
+fn relative_path(root: &Path, path: &str) -> Option<String> {
+    let relative = Path::new(path).strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+//按任务类型调用对应的上传下载
+async fn execute(app: &AppHandle, job: &TransferJob) -> Result<JobOutcome, String> {
+    let options = job.options.0.clone();
+    match job.kind {
+        TransferKind::UploadFile => {
+            let written = upload_hdfs_file(
+                app.clone(),
+                job.hdfs_config_id,
+                job.target.clone(),
+                job.source.clone(),
+                job.transfer_id.clone(),
+                options.upload,
+            )
+            .await?;
+            Ok(JobOutcome {
+                message: match written {
+                    Some(path) => format!("Uploaded to {}", path),
+                    None => "File exists, skipped".to_string(),
+                },
+                ..Default::default()
+            })
+        }
+        TransferKind::UploadFolder => {
+            let mut folder = options.folder.unwrap_or_default();
+            folder.skip_paths.extend(job.done_files.0.iter().cloned());
+            let report = upload_hdfs_folder(
+                app.clone(),
+                job.hdfs_config_id,
+                job.target.clone(),
+                job.source.clone(),
+                job.transfer_id.clone(),
+                folder,
+                options.upload,
+            )
+            .await?;
+            let root = Path::new(&job.source);
+            let failed: Vec<_> = report.files.iter().filter(|f| f.error.is_some()).collect();
+            Ok(JobOutcome {
+                failed: failed.len(),
+                error: failed
+                    .first()
+                    .map(|f| format!("{}: {}", f.local_path, f.error.clone().unwrap_or_default()))
+                    .unwrap_or_default(),
+                done_files: report
+                    .files
+                    .iter()
+                    .filter(|f| f.error.is_none())
+                    .filter_map(|f| relative_path(root, &f.local_path))
+                    .collect(),
+                message: format!(
+                    "Uploaded {} files, {} failed, {} skipped",
+                    report.uploaded, report.failed, report.skipped
+                ),
+            })
+        }
+        TransferKind::Download => {
+            //已下载的文件按大小和修改时间跳过,未完成的从.part续传
+            let report = download_folder(
+                app.clone(),
+                job.hdfs_config_id,
+                job.source.clone(),
+                job.target.clone(),
+                job.transfer_id.clone(),
+                options.download,
+            )
+            .await?;
+            let failed: Vec<_> = report.files.iter().filter(|f| f.error.is_some()).collect();
+            Ok(JobOutcome {
+                failed: failed.len(),
+                error: failed
+                    .first()
+                    .map(|f| format!("{}: {}", f.source_path, f.error.clone().unwrap_or_default()))
+                    .unwrap_or_default(),
+                message: format!(
+                    "Downloaded {} files, {} failed, {} skipped",
+                    report.downloaded, report.failed, report.skipped
+                ),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+//运行任务,定时保存进度,结束后按结果设置状态
+async fn run_job(app: AppHandle, mut job: TransferJob) {
+    keep_final_progress(&job.transfer_id);
+    let task = job.clone();
+    let run = execute(&app, &task);
+    tokio::pin!(run);
+    let mut interval = tokio::time::interval(SAVE_PROGRESS_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            _ = interval.tick() => {
+                //暂停或取消时传输可能还没有开始,每次都重新取消
+                let stop = RUNNING_JOBS
+                    .lock()
+                    .ok()
+                    .and_then(|jobs| jobs.get(&job.id).copied().flatten());
+                if stop.is_some() {
+                    let _ = cancel_transfer(job.transfer_id.clone()).await;
+                }
+                if let Some(progress) = transfer_progress(&job.transfer_id) {
+                    job.apply_progress(&progress);
+                    if let Err(e) = save_job(&job).await {
+                        log::warn!("Failed to save transfer job {}: {}", job.id, e);
+                    }
+                }
+            }
+        }
+    };
+    if let Some(progress) = take_final_progress(&job.transfer_id) {
+        job.apply_progress(&progress);
+    }
+    job.rate = 0.0;
+    job.eta_secs = None;
+    let stop = RUNNING_JOBS
+        .lock()
+        .ok()
+        .and_then(|mut jobs| jobs.remove(&job.id))
+        .flatten();
+
+    let error = match result {
+        Ok(outcome) => {
+            job.done_files.0.extend(outcome.done_files);
+            job.message = outcome.message;
+            (outcome.failed > 0).then(|| format!("{}, {}", job.message, outcome.error))
+        }
+        Err(e) => Some(e),
+    };
+    job.state = match (error, stop) {
+        (None, _) => JobState::Done,
+        //被暂停或取消,未完成的文件不算失败
+        (Some(_), Some(state)) => {
+            job.message = format!("{:?}", state);
+            state
+        }
+        (Some(e), None) if e.contains(TRANSFER_CANCELLED) => {
+            job.message = e;
+            JobState::Cancelled
+        }
+        (Some(e), None) if job.attempts < MAX_ATTEMPTS && !is_permanent_error(&e) => {
+            let delay = (RETRY_BASE_SECS << (job.attempts - 1).clamp(0, 16)).min(RETRY_MAX_SECS);
+            job.next_attempt_at = now_secs() + delay;
+            job.message = format!(
+                "{} (retry {} of {} in {}s)",
+                e,
+                job.attempts,
+                MAX_ATTEMPTS - 1,
+                delay
+            );
+            JobState::Queued
+        }
+        (Some(e), None) => {
+            job.message = e;
+            JobState::Failed
+        }
+    };
+    if job.state == JobState::Cancelled {
+        remove_partial_downloads(&job).await;
+    }
+    if let Err(e) = save_job(&job).await {
+        log::warn!("Failed to save transfer job {}: {}", job.id, e);
+    }
+    emit_job(&app, &job);
+    QUEUE_NOTIFY.notify_one();
+}
+
+//按并发数开始到期的排队任务
+async fn start_due_jobs(app: &AppHandle) -> Result<(), String> {
+    let free = concurrency().await?.saturating_sub(running_count());
+    if free == 0 {
+        return Ok(());
+    }
+    let jobs = sqlx::query_as::<_, TransferJob>(
+        "select * from transfer_job where state = 'queued' and next_attempt_at <= ? order by id limit ?",
+    )
+    .bind(now_secs())
+    .bind(free as i64)
+    .fetch_all(pool().await?)
+    .await
+    .map_err(|e| e.to_string())?;
+    for job in jobs {
+        //先登记为运行中,暂停和取消时据此决定是否需要停止传输
+        if let Ok(mut running) = RUNNING_JOBS.lock() {
+            running.insert(job.id, None);
+        }
+        let result = sqlx::query(
+            "update transfer_job set state = 'running', attempts = attempts + 1, updated_at = ? where id = ? and state = 'queued'",
+        )
+        .bind(chrono::Local::now().to_rfc3339())
+        .bind(job.id)
+        .execute(pool().await?)
+        .await;
+        if !matches!(&result, Ok(r) if r.rows_affected() > 0) {
+            if let Ok(mut running) = RUNNING_JOBS.lock() {
+                running.remove(&job.id);
+            }
+            result.map_err(|e| e.to_string())?;
+            continue;
+        }
+        let job = TransferJob {
+            transfer_id: job_transfer_id(job.id),
+            state: JobState::Running,
+            attempts: job.attempts + 1,
+            ..job
+        };
+        emit_job(app, &job);
+        tauri::async_runtime::spawn(run_job(app.clone(), job));
+    }
+    Ok(())
+}
+
+//启动传输队列,上次退出时运行中的任务重新排队
+pub fn spawn_transfer_queue(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let requeued: Result<(), String> = async {
+            sqlx::query("update transfer_job set state = 'queued' where state = 'running'")
+                .execute(pool().await?)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        .await;
+        if let Err(e) = requeued {
+            log::warn!("Failed to requeue transfer jobs: {}", e);
+        }
+        loop {
+            if let Err(e) = start_due_jobs(&app).await {
+                log::warn!("Failed to start transfer jobs: {}", e);
+            }
+            let _ = tokio::time::timeout(SCHEDULE_INTERVAL, QUEUE_NOTIFY.notified()).await;
+        }
+    });
+}
+
+//添加上传或下载任务,上传时source为本地路径、target为hdfs目录,下载时相反
+#[tauri::command]
+pub async fn enqueue_transfer_job(
+    app: AppHandle,
+    id: i64,
+    kind: TransferKind,
+    source: String,
+    target: String,
+    options: Option<TransferJobOptions>,
+) -> Result<TransferJob, String> {
+    let options = options.unwrap_or_default();
+    if source.is_empty() || target.is_empty() {
+        return Err("source and target are required".to_string());
+    }
+    if let Some(upload) = &options.upload {
+        upload.check()?;
+    }
+    let local = Path::new(match kind {
+        TransferKind::Download => &target,
+        _ => &source,
+    });
+    match kind {
+        TransferKind::UploadFile if !local.is_file() => {
+            return Err(format!("{} is not a file", local.display()));
+        }
+        TransferKind::UploadFolder | TransferKind::Download if !local.is_dir() => {
+            return Err(format!("{} is not a folder", local.display()));
+        }
+        _ => {}
+    }
+
+    let now = chrono::Local::now().to_rfc3339();
+    let result = sqlx::query(
+        "insert into transfer_job (hdfs_config_id, kind, source, target, options, state, created_at, updated_at) values (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(id)
+    .bind(kind)
+    .bind(&source)
+    .bind(&target)
+    .bind(Json(&options))
+    .bind(JobState::Queued)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool().await?)
+    .await
+    .map_err(|e| e.to_string())?;
+    let job = get_job(result.last_insert_rowid()).await?;
+    emit_job(&app, &job);
+    QUEUE_NOTIFY.notify_one();
+    Ok(job)
+}
+
+//任务列表,新的在前,运行中的任务带实时进度
+#[tauri::command]
+pub async fn list_transfer_jobs() -> Result<Vec<TransferJob>, String> {
+    let jobs = sqlx::query_as::<_, TransferJob>("select * from transfer_job order by id desc")
+        .fetch_all(pool().await?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(jobs
+        .into_iter()
+        .map(|job| {
+            let mut job = TransferJob {
+                transfer_id: job_transfer_id(job.id),
+                ..job
+            };
+            if let Some(progress) = transfer_progress(&job.transfer_id) {
+                job.apply_progress(&progress);
+            }
+            job
+        })
+        .collect())
+}
+
+//运行中的任务停止传输后设置为state,否则直接从from状态修改
+async fn stop_job(
+    app: &AppHandle,
+    job_id: i64,
+    from: &[JobState],
+    state: JobState,
+) -> Result<(), String> {
+    let running = {
+        let mut running = RUNNING_JOBS.lock().map_err(|e| e.to_string())?;
+        match running.get_mut(&job_id) {
+            Some(stop) => {
+                *stop = Some(state);
+                true
+            }
+            None => false,
+        }
+    };
+    if running {
+        cancel_transfer(job_transfer_id(job_id)).await?;
+        return Ok(());
+    }
+    if !change_state(job_id, from, state).await? {
+        let job = get_job(job_id).await?;
+        return Err(format!("Transfer job {} is {:?}", job_id, job.state));
+    }
+    if state == JobState::Cancelled {
+        remove_partial_downloads(&get_job(job_id).await?).await;
+    }
+    emit_job_by_id(app, job_id).await;
+    Ok(())
+}
+
+//暂停任务,下载的文件保留.part以便续传,上传目录时跳过已完成的文件
+//上传单个文件时不续传,继续后从头重新上传
+#[tauri::command]
+pub async fn pause_transfer_job(app: AppHandle, job_id: i64) -> Result<(), String> {
+    stop_job(&app, job_id, &[JobState::Queued], JobState::Paused).await
+}
+
+//取消任务,下载任务删除已下载的.part文件
+#[tauri::command]
+pub async fn cancel_transfer_job(app: AppHandle, job_id: i64) -> Result<(), String> {
+    stop_job(
+        &app,
+        job_id,
+        &[JobState::Queued, JobState::Paused],
+        JobState::Cancelled,
+    )
+    .await
+}
+
+//继续已暂停的任务
+#[tauri::command]
+pub async fn resume_transfer_job(app: AppHandle, job_id: i64) -> Result<(), String> {
+    requeue_job(&app, job_id, &[JobState::Paused]).await
+}
+
+//重试失败或取消的任务,等待重试的任务立即开始
+#[tauri::command]
+pub async fn retry_transfer_job(app: AppHandle, job_id: i64) -> Result<(), String> {
+    requeue_job(
+        &app,
+        job_id,
+        &[JobState::Failed, JobState::Cancelled, JobState::Queued],
+    )
+    .await
+}
+
+async fn requeue_job(app: &AppHandle, job_id: i64, from: &[JobState]) -> Result<(), String> {
+    if !change_state(job_id, from, JobState::Queued).await? {
+        let job = get_job(job_id).await?;
+        return Err(format!("Transfer job {} is {:?}", job_id, job.state));
+    }
+    emit_job_by_id(app, job_id).await;
+    QUEUE_NOTIFY.notify_one();
+    Ok(())
+}
+
+//删除没有在运行的任务
+#[tauri::command]
+pub async fn remove_transfer_job(job_id: i64) -> Result<(), String> {
+    let result = sqlx::query("delete from transfer_job where id = ? and state != 'running'")
+        .bind(job_id)
+        .execute(pool().await?)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Transfer job {} is running or not found", job_id));
+    }
+    Ok(())
+}
+
+//同时运行的任务数
+#[tauri::command]
+pub async fn get_transfer_queue_concurrency() -> Result<usize, String> {
+    concurrency().await
+}
+
+#[tauri::command]
+pub async fn set_transfer_queue_concurrency(concurrency: usize) -> Result<usize, String> {
+    let concurrency = concurrency.clamp(1, MAX_CONCURRENCY);
+    sqlx::query("insert or replace into app_setting (key, value) values (?, ?)")
+        .bind(CONCURRENCY_SETTING)
+        .bind(concurrency.to_string())
+        .execute(pool().await?)
+        .await
+        .map_err(|e| e.to_string())?;
+    QUEUE_NOTIFY.notify_one();
+    Ok(concurrency)
+}
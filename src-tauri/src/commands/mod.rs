@@ -1,5 +1,13 @@
 //hdfs连接配置
 pub mod hdfs_config;
+//hdfs连接配置校验
+pub mod hdfs_config_validate;
+//从hadoop配置文件导入hdfs连接配置
+pub mod hdfs_config_xml;
+//hdfs连接配置导入导出
+pub mod hdfs_config_bundle;
+//hdfs连接配置加密
+pub mod hdfs_config_secret;
 //hdfs文件操作
 pub mod hdfs_file;
 //hdfs acls操作
@@ -12,3 +20,18 @@ pub mod hdfs_parquet;
 
 //hdfs avro文件操作
 pub mod hdfs_avro;
+
+//hdfs连接测试
+pub mod hdfs_connection;
+
+//kerberos票据
+pub mod hdfs_kerberos;
+
+//集群状态
+pub mod hdfs_cluster;
+
+//上传下载进度和取消
+pub mod hdfs_transfer;
+
+//持久化的上传下载队列
+pub mod hdfs_transfer_queue;
@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use tauri::Manager;
+
+use super::db_init::DB_FILE_NAME;
+
+//指定数据目录的环境变量
+pub static DATA_DIR_ENV: &str = "HDFS_GUI_DATA_DIR";
+//指定数据目录的命令行参数 --data-dir <path> 或 --data-dir=<path>
+pub static DATA_DIR_ARG: &str = "--data-dir";
+
+//命令行参数或环境变量指定的数据目录,命令行优先
+pub fn data_dir_override() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_ARG {
+            if let Some(dir) = args.next() {
+                return Some(PathBuf::from(dir));
+            }
+        } else if let Some(dir) = arg.strip_prefix(&format!("{}=", DATA_DIR_ARG)) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    std::env::var(DATA_DIR_ENV)
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+//获取数据目录,默认使用系统的app-data目录
+pub fn resolve_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = match data_dir_override() {
+        Some(dir) => dir,
+        None => app.path().app_data_dir().map_err(|e| e.to_string())?,
+    };
+    std::fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    migrate_legacy_db(&data_dir)?;
+    Ok(data_dir)
+}
+
+//旧版本把数据库放在当前工作目录,首次启动时复制到数据目录
+fn migrate_legacy_db(data_dir: &Path) -> Result<(), String> {
+    let target = data_dir.join(DB_FILE_NAME);
+    if target.exists() {
+        return Ok(());
+    }
+    let Ok(current_dir) = std::env::current_dir() else {
+        return Ok(());
+    };
+    let legacy = current_dir.join(DB_FILE_NAME);
+    if !legacy.is_file() || legacy == target {
+        return Ok(());
+    }
+
+    log::info!(
+        "Copying database {} to {}",
+        legacy.display(),
+        target.display()
+    );
+    std::fs::copy(&legacy, &target).map_err(|e| e.to_string())?;
+    //sqlite wal模式下未合并的数据
+    for suffix in ["-wal", "-shm"] {
+        let legacy_extra = current_dir.join(format!("{}{}", DB_FILE_NAME, suffix));
+        if legacy_extra.is_file() {
+            std::fs::copy(
+                &legacy_extra,
+                data_dir.join(format!("{}{}", DB_FILE_NAME, suffix)),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
@@ -1,30 +1,54 @@
+use std::path::PathBuf;
+
 use once_cell::sync::OnceCell;
-use sqlx::{migrate::MigrateDatabase, Pool, Sqlite};
+use sqlx::{Pool, Sqlite, sqlite::SqliteConnectOptions};
+
+pub static DB_FILE_NAME: &str = "hdfs-gui.db";
 
-pub static DB_FILE: &str = "sqlite://hdfs-gui.db";
+//数据库所在目录,启动时设置
+static DB_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+pub fn set_db_dir(dir: PathBuf) -> Result<(), anyhow::Error> {
+    DB_DIR
+        .set(dir)
+        .map_err(|_| anyhow::anyhow!("set db dir fail".to_string()))
+}
+
+//数据库文件路径,未设置目录时使用当前目录
+pub fn db_file_path() -> PathBuf {
+    DB_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(DB_FILE_NAME)
+}
 
 pub static DB_POOL: OnceCell<Pool<Sqlite>> = OnceCell::new();
+//初始化锁,保证数据库升级完成前其他命令不会使用连接池
+static INIT_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 //数据库连接池初始化
 pub async fn init_db() -> Result<(), anyhow::Error> {
-    //创建数据库
-    if !Sqlite::database_exists(DB_FILE).await.unwrap_or(false) {
-        log::info!("Creating database {}", DB_FILE);
-        match Sqlite::create_database(DB_FILE).await {
-            Ok(_) => log::info!("Create db success"),
-            Err(error) => panic!("error: {}", error),
-        }
-    } else {
-        log::info!("Database already exists");
-    }
+    let _guard = INIT_LOCK.lock().await;
 
     if DB_POOL.get().is_none() {
-        let pool = Pool::<Sqlite>::connect(DB_FILE).await?;
+        let db_file = db_file_path();
+        //创建数据库
+        if !db_file.exists() {
+            log::info!("Creating database {}", db_file.display());
+        } else {
+            log::info!("Database already exists");
+        }
+        let options = SqliteConnectOptions::new()
+            .filename(&db_file)
+            .create_if_missing(true);
+        let pool = Pool::<Sqlite>::connect_with(options).await?;
+        super::migrations::run_migrations(&pool, db_file.to_str().unwrap_or_default()).await?;
         DB_POOL
             .set(pool)
             .map_err(|_| anyhow::anyhow!("set pool fail".to_string()))?;
-        if let Some(init_pool) = DB_POOL.get() {
-            sqlx::query("CREATE TABLE if not exists hdfs_config (id INTEGER PRIMARY KEY   AUTOINCREMENT, name TEXT, hdfs_url TEXT,hdfs_config TEXT, del_flag INTEGER)")
-        .execute(init_pool).await.map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        //旧版本保存的明文敏感值
+        if let Err(e) = super::secret::migrate_plain_configs().await {
+            log::warn!("Encrypt plain config values error: {}", e);
         }
     }
 
@@ -0,0 +1,125 @@
+use sqlx::{Pool, Row, Sqlite};
+
+//数据库版本升级脚本,version必须递增,已发布的脚本不要再修改
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create hdfs_config",
+        sql: "CREATE TABLE if not exists hdfs_config (id INTEGER PRIMARY KEY   AUTOINCREMENT, name TEXT, hdfs_url TEXT,hdfs_config TEXT, del_flag INTEGER)",
+    },
+    Migration {
+        version: 2,
+        description: "create app_setting",
+        sql: "CREATE TABLE if not exists app_setting (key TEXT PRIMARY KEY, value TEXT)",
+    },
+    Migration {
+        version: 3,
+        description: "add hdfs_config.proxy_user",
+        sql: "ALTER TABLE hdfs_config ADD COLUMN proxy_user TEXT not null default ''",
+    },
+    Migration {
+        version: 4,
+        description: "create transfer_job",
+        sql: "CREATE TABLE if not exists transfer_job (id INTEGER PRIMARY KEY AUTOINCREMENT, hdfs_config_id INTEGER not null, kind TEXT not null, source TEXT not null, target TEXT not null, options TEXT not null default '{}', state TEXT not null, bytes INTEGER not null default 0, total INTEGER not null default 0, files_done INTEGER not null default 0, files_total INTEGER not null default 0, attempts INTEGER not null default 0, next_attempt_at INTEGER not null default 0, done_files TEXT not null default '[]', message TEXT not null default '', created_at TEXT not null, updated_at TEXT not null)",
+    },
+];
+
+//当前程序支持的最新版本
+pub fn latest_version() -> i64 {
+    MIGRATIONS.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+async fn ensure_schema_version_table(pool: &Pool<Sqlite>) -> Result<(), anyhow::Error> {
+    sqlx::query(
+        "CREATE TABLE if not exists schema_version (version INTEGER PRIMARY KEY, description TEXT, applied_at TEXT)",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+//获取当前数据库版本,没有升级记录时为0
+pub async fn current_version(pool: &Pool<Sqlite>) -> Result<i64, anyhow::Error> {
+    ensure_schema_version_table(pool).await?;
+    let row = sqlx::query("select coalesce(max(version), 0) as version from schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.try_get::<i64, _>("version")?)
+}
+
+//是否已有用户数据表(区分新库与未记录版本的旧库)
+async fn has_user_tables(pool: &Pool<Sqlite>) -> Result<bool, anyhow::Error> {
+    let row = sqlx::query(
+        "select count(*) as cnt from sqlite_master where type = 'table' and name not like 'sqlite_%' and name != 'schema_version'",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.try_get::<i64, _>("cnt")? > 0)
+}
+
+//升级前备份数据库
+async fn backup_db(
+    pool: &Pool<Sqlite>,
+    db_file_path: &str,
+    version: i64,
+) -> Result<String, anyhow::Error> {
+    let backup_path = format!(
+        "{}.v{}.{}.bak",
+        db_file_path,
+        version,
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    );
+    sqlx::query("VACUUM INTO ?")
+        .bind(&backup_path)
+        .execute(pool)
+        .await?;
+    Ok(backup_path)
+}
+
+//执行未应用的升级脚本,存在旧数据时先备份
+pub async fn run_migrations(pool: &Pool<Sqlite>, db_file_path: &str) -> Result<(), anyhow::Error> {
+    let version = current_version(pool).await?;
+    if version > latest_version() {
+        return Err(anyhow::anyhow!(
+            "Database version {} is newer than the supported version {}, please upgrade hdfs-gui",
+            version,
+            latest_version()
+        ));
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > version).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    if has_user_tables(pool).await? {
+        let backup_path = backup_db(pool, db_file_path, version).await?;
+        log::info!("Backup database version {} to {}", version, backup_path);
+    }
+
+    for migration in pending {
+        log::info!(
+            "Migrating database to version {}: {}",
+            migration.version,
+            migration.description
+        );
+        let mut tx = pool.begin().await?;
+        sqlx::raw_sql(migration.sql).execute(&mut *tx).await?;
+        sqlx::query(
+            "insert into schema_version (version, description, applied_at) values (?, ?, ?)",
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(chrono::Local::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
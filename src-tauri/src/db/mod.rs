@@ -1 +1,7 @@
 pub mod db_init;
+//数据库版本升级
+pub mod migrations;
+//数据目录
+pub mod data_dir;
+//配置加密
+pub mod secret;
@@ -0,0 +1,490 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+use super::db_init::{DB_POOL, db_file_path};
+
+//加密值前缀
+pub static ENCRYPTED_PREFIX: &str = "enc:v1:";
+//返回给前端时敏感值的掩码,保存时遇到掩码保留原值
+pub static MASKED_VALUE: &str = "******";
+
+//指定主密钥文件的环境变量
+pub static MASTER_KEY_FILE_ENV: &str = "HDFS_GUI_MASTER_KEY_FILE";
+pub static MASTER_KEY_FILE_NAME: &str = "master.key";
+
+//需要加密的配置项
+pub const SENSITIVE_KEYS: [&str; 3] = [
+    "dfs.namenode.keytab.file",
+    "dfs.namenode.kerberos.principal",
+    crate::backend::webhdfs::DELEGATION_TOKEN_KEY,
+];
+//最后一段为这些词的配置项也视为敏感,如ssl.client.truststore.password
+//不按子串匹配,dfs.block.access.token.enable等开关不是敏感值
+pub const SENSITIVE_KEY_SUFFIXES: [&str; 2] = ["password", "secret"];
+
+//app_setting中的配置项
+static SOURCE_SETTING: &str = "secret.key_source";
+static SALT_SETTING: &str = "secret.key_salt";
+static CHECK_SETTING: &str = "secret.key_check";
+static CHECK_PLAIN: &str = "hdfs-gui";
+
+const PBKDF2_ROUNDS: u32 = 200_000;
+
+//当前会话已加载的主密钥
+static MASTER_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+//第一次加载主密钥时加锁,避免同时生成多个不同的密钥文件
+static MASTER_KEY_LOAD: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+//主密钥来源
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretKeySource {
+    //密钥文件
+    File,
+    //口令派生
+    Passphrase,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SecretStoreStatus {
+    pub source: SecretKeySource,
+    pub unlocked: bool,
+    pub key_file: String,
+}
+
+pub fn is_sensitive_key(key: &str) -> bool {
+    let last = key.rsplit('.').next().unwrap_or_default().to_lowercase();
+    SENSITIVE_KEYS.contains(&key) || SENSITIVE_KEY_SUFFIXES.contains(&last.as_str())
+}
+
+pub fn master_key_file() -> PathBuf {
+    match std::env::var(MASTER_KEY_FILE_ENV) {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => db_file_path()
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(MASTER_KEY_FILE_NAME),
+    }
+}
+
+fn pool() -> Result<&'static Pool<Sqlite>, String> {
+    DB_POOL
+        .get()
+        .ok_or_else(|| "Database connection pool is not initialized".to_owned())
+}
+
+async fn get_setting(key: &str) -> Result<Option<String>, String> {
+    sqlx::query_scalar::<_, String>("select value from app_setting where key = ?")
+        .bind(key)
+        .fetch_optional(pool()?)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn key_source() -> Result<SecretKeySource, String> {
+    Ok(match get_setting(SOURCE_SETTING).await?.as_deref() {
+        Some("passphrase") => SecretKeySource::Passphrase,
+        _ => SecretKeySource::File,
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn read_key_file(path: &PathBuf) -> Result<[u8; 32], String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let bytes = BASE64
+        .decode(content.trim())
+        .map_err(|e| format!("Invalid master key file {}: {}", path.display(), e))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("Invalid master key length in {}", path.display()))
+}
+
+fn write_key_file(path: &PathBuf, key: &[u8; 32]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, BASE64.encode(key)).map_err(|e| e.to_string())
+}
+
+pub fn encrypt_value(key: &[u8; 32], plain: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce_bytes = random_bytes::<12>();
+    let mut data = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plain.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let mut out = nonce_bytes.to_vec();
+    out.append(&mut data);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(out)))
+}
+
+//未加密的值原样返回
+pub fn decrypt_value(key: &[u8; 32], value: &str) -> Result<String, String> {
+    let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let data = BASE64.decode(encoded).map_err(|e| e.to_string())?;
+    if data.len() < 12 {
+        return Err("Invalid encrypted value".to_owned());
+    }
+    let cipher = Aes256Gcm::new(key.into());
+    let plain = cipher
+        .decrypt(Nonce::from_slice(&data[..12]), &data[12..])
+        .map_err(|_| "Failed to decrypt value, the master key does not match".to_owned())?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+//更换密钥时新密钥文件的路径,替换失败时留在这里
+fn new_key_file(key_file: &std::path::Path) -> PathBuf {
+    key_file.with_extension("key.new")
+}
+
+//密钥文件与数据库不一致时,检查是否为更换密钥后没有替换成功的新密钥文件
+fn recover_new_key_file(path: &PathBuf, check: &str) -> Option<[u8; 32]> {
+    let new_path = new_key_file(path);
+    let key = read_key_file(&new_path).ok()?;
+    decrypt_value(&key, check).ok()?;
+    log::warn!(
+        "Master key file {} does not match the database, using {}",
+        path.display(),
+        new_path.display()
+    );
+    //旧密钥文件备份失败时不替换,下次仍从临时文件恢复
+    let backup = if path.exists() {
+        std::fs::copy(path, path.with_extension("key.bak")).map(|_| ())
+    } else {
+        Ok(())
+    };
+    if let Err(e) = backup {
+        log::warn!("Failed to back up {}: {}", path.display(), e);
+        return Some(key);
+    }
+    if let Err(e) = std::fs::rename(&new_path, path) {
+        log::warn!("Failed to rename {}: {}", new_path.display(), e);
+    }
+    Some(key)
+}
+
+//加载主密钥,文件模式下第一次使用时自动生成密钥文件
+pub async fn master_key() -> Result<[u8; 32], String> {
+    if let Some(key) = *MASTER_KEY.lock().map_err(|e| e.to_string())? {
+        return Ok(key);
+    }
+    let _load = MASTER_KEY_LOAD.lock().await;
+    if let Some(key) = *MASTER_KEY.lock().map_err(|e| e.to_string())? {
+        return Ok(key);
+    }
+    match key_source().await? {
+        SecretKeySource::Passphrase => {
+            Err("Secret store is locked, please enter the master passphrase".to_owned())
+        }
+        SecretKeySource::File => {
+            let path = master_key_file();
+            let check = get_setting(CHECK_SETTING).await?;
+            let recovered = check
+                .as_deref()
+                .filter(|check| {
+                    !read_key_file(&path).is_ok_and(|key| decrypt_value(&key, check).is_ok())
+                })
+                .and_then(|check| recover_new_key_file(&path, check));
+            let key = if let Some(key) = recovered {
+                key
+            } else if path.exists() {
+                read_key_file(&path)?
+            } else if check.is_some() {
+                return Err(format!("Master key file {} not found", path.display()));
+            } else {
+                log::info!("Creating master key file {}", path.display());
+                let key = random_bytes::<32>();
+                write_key_file(&path, &key)?;
+                key
+            };
+            if let Some(check) = check {
+                decrypt_value(&key, &check).map_err(|_| {
+                    format!(
+                        "Master key file {} does not match the database",
+                        path.display()
+                    )
+                })?;
+            } else {
+                let mut conn = pool()?.acquire().await.map_err(|e| e.to_string())?;
+                set_settings(&mut conn, SecretKeySource::File, None, &key).await?;
+            }
+            *MASTER_KEY.lock().map_err(|e| e.to_string())? = Some(key);
+            Ok(key)
+        }
+    }
+}
+
+async fn set_settings(
+    conn: &mut SqliteConnection,
+    source: SecretKeySource,
+    salt: Option<&[u8]>,
+    key: &[u8; 32],
+) -> Result<(), String> {
+    let source_value = match source {
+        SecretKeySource::File => "file",
+        SecretKeySource::Passphrase => "passphrase",
+    };
+    let settings = [
+        (SOURCE_SETTING, source_value.to_string()),
+        (
+            SALT_SETTING,
+            salt.map(|s| BASE64.encode(s)).unwrap_or_default(),
+        ),
+        (CHECK_SETTING, encrypt_value(key, CHECK_PLAIN)?),
+    ];
+    for (key, value) in settings {
+        sqlx::query("insert or replace into app_setting (key, value) values (?, ?)")
+            .bind(key)
+            .bind(value)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+//口令模式下解锁
+pub async fn unlock(passphrase: &str) -> Result<(), String> {
+    if key_source().await? != SecretKeySource::Passphrase {
+        return Err("Secret store does not use a passphrase".to_owned());
+    }
+    let salt = BASE64
+        .decode(get_setting(SALT_SETTING).await?.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    let key = derive_key(passphrase, &salt);
+    let check = get_setting(CHECK_SETTING).await?.unwrap_or_default();
+    decrypt_value(&key, &check).map_err(|_| "Wrong master passphrase".to_owned())?;
+    *MASTER_KEY.lock().map_err(|e| e.to_string())? = Some(key);
+    migrate_plain_configs().await
+}
+
+pub async fn status() -> Result<SecretStoreStatus, String> {
+    Ok(SecretStoreStatus {
+        source: key_source().await?,
+        unlocked: MASTER_KEY.lock().map_err(|e| e.to_string())?.is_some(),
+        key_file: master_key_file().to_str().unwrap_or_default().to_string(),
+    })
+}
+
+//对配置json中的每个字符串值调用f,由f判断是否敏感
+fn map_string_values(
+    hdfs_config: &str,
+    f: impl Fn(&str, &str) -> Result<String, String>,
+) -> Result<String, String> {
+    let Ok(mut config_json) =
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(hdfs_config)
+    else {
+        //不是json对象时原样保存,与旧版本行为一致
+        return Ok(hdfs_config.to_string());
+    };
+    for (key, value) in config_json.iter_mut() {
+        if let serde_json::Value::String(s) = value {
+            *s = f(key, s)?;
+        }
+    }
+    serde_json::to_string_pretty(&config_json).map_err(|e| e.to_string())
+}
+
+//加密配置json中的敏感值,不再视为敏感的key中的旧加密值解密保存
+pub fn encrypt_config_json(key: &[u8; 32], hdfs_config: &str) -> Result<String, String> {
+    map_string_values(hdfs_config, |k, v| {
+        if !is_sensitive_key(k) {
+            decrypt_value(key, v)
+        } else if v.starts_with(ENCRYPTED_PREFIX) {
+            Ok(v.to_string())
+        } else {
+            encrypt_value(key, v)
+        }
+    })
+}
+
+//解密配置json中的全部加密值
+pub fn decrypt_config_json(key: &[u8; 32], hdfs_config: &str) -> Result<String, String> {
+    map_string_values(hdfs_config, |_, v| decrypt_value(key, v))
+}
+
+//是否有未加密的敏感值,或不再视为敏感的key中有加密值
+fn needs_encryption_migration(hdfs_config: &str) -> bool {
+    let Ok(config_json) =
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(hdfs_config)
+    else {
+        return false;
+    };
+    config_json.iter().any(|(k, v)| {
+        v.as_str().is_some_and(|v| {
+            !v.is_empty() && is_sensitive_key(k) != v.starts_with(ENCRYPTED_PREFIX)
+        })
+    })
+}
+
+//加密旧版本保存的明文敏感值,启动时和口令解锁后执行
+//口令模式未解锁时跳过,解锁后再执行
+pub async fn migrate_plain_configs() -> Result<(), String> {
+    let pool = pool()?;
+    let rows: Vec<(i64, String)> = sqlx::query_as("select id, hdfs_config from hdfs_config")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(i64, String)> = rows
+        .into_iter()
+        .filter(|(_, hdfs_config)| needs_encryption_migration(hdfs_config))
+        .collect();
+    if rows.is_empty() {
+        return Ok(());
+    }
+    if key_source().await? == SecretKeySource::Passphrase
+        && MASTER_KEY.lock().map_err(|e| e.to_string())?.is_none()
+    {
+        log::info!("Secret store is locked, plain config values are encrypted after unlock");
+        return Ok(());
+    }
+    let key = master_key().await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for (id, hdfs_config) in &rows {
+        sqlx::query("update hdfs_config set hdfs_config = ? where id = ?")
+            .bind(encrypt_config_json(&key, hdfs_config)?)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    log::info!("Encrypted sensitive values of {} connections", rows.len());
+    Ok(())
+}
+
+//读取配置时解密,没有加密值时不需要主密钥
+pub async fn decrypt_config(hdfs_config: &str) -> Result<String, String> {
+    if !hdfs_config.contains(ENCRYPTED_PREFIX) {
+        return Ok(hdfs_config.to_string());
+    }
+    let key = master_key().await?;
+    decrypt_config_json(&key, hdfs_config)
+}
+
+//保存配置时加密
+pub async fn encrypt_config(hdfs_config: &str) -> Result<String, String> {
+    let key = master_key().await?;
+    encrypt_config_json(&key, hdfs_config)
+}
+
+//敏感值替换为掩码
+pub fn mask_config_json(hdfs_config: &str) -> String {
+    map_string_values(hdfs_config, |k, v| {
+        //口令模式未解锁时旧的加密值还未迁移,也不显示
+        let masked = (is_sensitive_key(k) && !v.is_empty()) || v.starts_with(ENCRYPTED_PREFIX);
+        Ok(if masked {
+            MASKED_VALUE.to_string()
+        } else {
+            v.to_string()
+        })
+    })
+    .unwrap_or_else(|_| hdfs_config.to_string())
+}
+
+//保存时掩码值还原为原配置中的值
+pub fn unmask_config_json(hdfs_config: &str, old_hdfs_config: &str) -> Result<String, String> {
+    let old_json =
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(old_hdfs_config)
+            .unwrap_or_default();
+    map_string_values(hdfs_config, |k, v| {
+        if v == MASKED_VALUE {
+            Ok(old_json
+                .get(k)
+                .and_then(|old| old.as_str())
+                .unwrap_or_default()
+                .to_string())
+        } else {
+            Ok(v.to_string())
+        }
+    })
+}
+
+//更换主密钥:用旧密钥解密全部配置,再用新密钥加密
+pub async fn rotate(passphrase: Option<String>) -> Result<(), String> {
+    let old_key = master_key().await?;
+    let (source, salt, new_key) = match &passphrase {
+        Some(passphrase) if !passphrase.is_empty() => {
+            let salt = random_bytes::<16>();
+            let key = derive_key(passphrase, &salt);
+            (SecretKeySource::Passphrase, Some(salt), key)
+        }
+        _ => (SecretKeySource::File, None, random_bytes::<32>()),
+    };
+
+    let pool = pool()?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let rows: Vec<(i64, String)> = sqlx::query_as("select id, hdfs_config from hdfs_config")
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for (id, hdfs_config) in rows {
+        let plain = decrypt_config_json(&old_key, &hdfs_config)?;
+        sqlx::query("update hdfs_config set hdfs_config = ? where id = ?")
+            .bind(encrypt_config_json(&new_key, &plain)?)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    set_settings(
+        &mut tx,
+        source,
+        salt.as_ref().map(|s| s.as_slice()),
+        &new_key,
+    )
+    .await?;
+
+    //新密钥文件先写临时文件,提交成功后再替换
+    //替换失败时数据库已经用新密钥加密,下次加载主密钥时从临时文件恢复
+    let key_file = master_key_file();
+    let new_key_file = new_key_file(&key_file);
+    if source == SecretKeySource::File {
+        write_key_file(&new_key_file, &new_key)?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    *MASTER_KEY.lock().map_err(|e| e.to_string())? = Some(new_key);
+    if source == SecretKeySource::File {
+        if key_file.exists() {
+            std::fs::copy(&key_file, key_file.with_extension("key.bak")).map_err(|e| {
+                format!(
+                    "Master key rotated, but backing up {} failed, the new key is kept in {}: {}",
+                    key_file.display(),
+                    new_key_file.display(),
+                    e
+                )
+            })?;
+        }
+        std::fs::rename(&new_key_file, &key_file).map_err(|e| {
+            format!(
+                "Master key rotated, but replacing {} failed, the new key is kept in {}: {}",
+                key_file.display(),
+                new_key_file.display(),
+                e
+            )
+        })?;
+    }
+    log::info!("Master key rotated");
+    Ok(())
+}
@@ -0,0 +1,377 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use awol2005ex_kerbeiros::Credential;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::db::db_init::db_file_path;
+
+/// Directory holding the per-connection caches, next to the database
+const CCACHE_DIR_NAME: &str = "ccache";
+
+/// `KRB5CCNAME` as set when the app started, restored for connections
+/// without a cache of their own so an external `kinit` still works.
+static ORIGINAL_KRB5CCNAME: Lazy<Option<OsString>> = Lazy::new(|| std::env::var_os("KRB5CCNAME"));
+
+/// Held for writing while `KRB5CCNAME`/`HADOOP_PROXY_USER` are changed and for
+/// reading around GSSAPI calls, since the C library reads the environment
+/// without going through Rust's environment lock.
+pub static ENV_LOCK: RwLock<()> = RwLock::new(());
+
+pub fn ccache_dir() -> PathBuf {
+    db_file_path()
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(CCACHE_DIR_NAME)
+}
+
+/// Credential cache file of a connection
+pub fn ccache_path(id: i64) -> PathBuf {
+    ccache_dir().join(format!("krb5cc_{}", id))
+}
+
+/// Write a connection's TGT to its own cache file, readable by the owner only
+pub fn save_credential(id: i64, credential: &Credential) -> Result<PathBuf, String> {
+    let path = ccache_path(id);
+    std::fs::create_dir_all(ccache_dir()).map_err(|e| e.to_string())?;
+    credential
+        .save_into_ccache_file(path.to_str().unwrap_or_default())
+        .map_err(|e| format!("Failed to save credential cache: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(path)
+}
+
+/// Remove a connection's cache file, if any
+pub fn remove_ccache(id: i64) {
+    let path = ccache_path(id);
+    match std::fs::remove_file(&path) {
+        Ok(()) => log::info!("Removed credential cache {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => log::warn!("Failed to remove {}: {}", path.display(), e),
+    }
+}
+
+/// The cache a connection authenticates with: its own file when it has
+/// logged in, otherwise the cache the app was started with.
+///
+/// WebHDFS passes it per request; RPC clients only read it from `KRB5CCNAME`
+/// when connecting, see `hdfs_connection::new_rpc_client`.
+pub fn connection_ccache(id: i64) -> Option<OsString> {
+    let path = ccache_path(id);
+    if path.is_file() {
+        let mut name = OsString::from("FILE:");
+        name.push(path.as_os_str());
+        Some(name)
+    } else {
+        ORIGINAL_KRB5CCNAME.clone()
+    }
+}
+
+/// End time of the TGT a connection authenticates with, if its cache is readable
+pub fn connection_tgt_end_time(id: i64) -> Option<i64> {
+    let path = resolve_ccache_name(connection_ccache(id).as_ref()).ok()?;
+    read_ccache(&path).ok()?.tgt().map(|tgt| tgt.end_time)
+}
+
+/// Ticket flags with their `klist -f` letters, most significant bit first
+const TICKET_FLAGS: [(u32, char, &str); 14] = [
+    (0x4000_0000, 'F', "forwardable"),
+    (0x2000_0000, 'f', "forwarded"),
+    (0x1000_0000, 'P', "proxiable"),
+    (0x0800_0000, 'p', "proxy"),
+    (0x0400_0000, 'D', "may-postdate"),
+    (0x0200_0000, 'd', "postdated"),
+    (0x0100_0000, 'i', "invalid"),
+    (0x0080_0000, 'R', "renewable"),
+    (0x0040_0000, 'I', "initial"),
+    (0x0020_0000, 'A', "pre-authent"),
+    (0x0010_0000, 'H', "hw-authent"),
+    (0x0008_0000, 'T', "transit-policy-checked"),
+    (0x0004_0000, 'O', "ok-as-delegate"),
+    (0x0000_8000, 'a', "anonymous"),
+];
+
+/// `klist -f` style flag letters, e.g. `FRIA`
+pub fn flag_letters(flags: u32) -> String {
+    TICKET_FLAGS
+        .iter()
+        .filter(|(bit, _, _)| flags & bit != 0)
+        .map(|(_, letter, _)| *letter)
+        .collect()
+}
+
+pub fn flag_names(flags: u32) -> Vec<String> {
+    TICKET_FLAGS
+        .iter()
+        .filter(|(bit, _, _)| flags & bit != 0)
+        .map(|(_, _, name)| name.to_string())
+        .collect()
+}
+
+/// The ccache file behind a `KRB5CCNAME` value. `FILE:` and `DIR:` caches
+/// are supported; without a name the platform default cache is used.
+pub fn resolve_ccache_name(name: Option<&OsString>) -> Result<PathBuf, String> {
+    let Some(name) = name else {
+        let default = default_ccache_name()?;
+        return resolve_ccache_name(Some(&default)).map_err(|e| {
+            format!(
+                "KRB5CCNAME is not set and the default cache {} is not readable: {}",
+                default.to_string_lossy(),
+                e
+            )
+        });
+    };
+    let name = name.to_string_lossy();
+    let (kind, residual) = match name.split_once(':') {
+        // a Windows drive letter is not a cache type
+        Some((kind, residual)) if kind.len() > 1 => (kind.to_uppercase(), residual),
+        _ => ("FILE".to_string(), name.as_ref()),
+    };
+    match kind.as_str() {
+        "FILE" => Ok(PathBuf::from(residual)),
+        "DIR" => {
+            // DIR::/dir/tkt names a cache directly, DIR:/dir uses its primary cache
+            if let Some(file) = residual.strip_prefix(':') {
+                return Ok(PathBuf::from(file));
+            }
+            let dir = PathBuf::from(residual);
+            let primary = std::fs::read_to_string(dir.join("primary"))
+                .map(|p| p.trim().to_string())
+                .unwrap_or_else(|_| "tkt".to_string());
+            Ok(dir.join(primary))
+        }
+        _ => Err(format!(
+            "Credential cache type {} is not supported, only FILE: and DIR:",
+            kind
+        )),
+    }
+}
+
+/// macOS keeps tickets in the CCAPI store (`API:`), which has no file
+#[cfg(target_os = "macos")]
+fn default_ccache_name() -> Result<OsString, String> {
+    Ok(OsString::from("API:"))
+}
+
+/// MIT's default `FILE:/tmp/krb5cc_<uid>`
+#[cfg(all(unix, not(target_os = "macos")))]
+fn default_ccache_name() -> Result<OsString, String> {
+    // Safety: getuid has no preconditions and always succeeds
+    let uid = unsafe { libc::getuid() };
+    Ok(OsString::from(format!("FILE:/tmp/krb5cc_{}", uid)))
+}
+
+#[cfg(not(unix))]
+fn default_ccache_name() -> Result<OsString, String> {
+    Err("KRB5CCNAME is not set".to_string())
+}
+
+/// A principal as stored in a ccache file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CcachePrincipal {
+    pub name_type: u32,
+    pub realm: String,
+    pub components: Vec<String>,
+}
+
+impl std::fmt::Display for CcachePrincipal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.components.join("/"), self.realm)
+    }
+}
+
+/// One credential of a ccache file; times are unix seconds
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CcacheCredential {
+    pub client: CcachePrincipal,
+    pub server: CcachePrincipal,
+    pub enctype: u16,
+    pub auth_time: i64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub renew_till: i64,
+    pub is_skey: bool,
+    pub flags: u32,
+}
+
+impl CcacheCredential {
+    /// Whether this is a TGT (`krbtgt/REALM@REALM`)
+    pub fn is_tgt(&self) -> bool {
+        self.server.components.first().map(|c| c.as_str()) == Some("krbtgt")
+    }
+
+    /// MIT stores cache configuration as fake credentials in this realm
+    pub fn is_config_entry(&self) -> bool {
+        self.server.realm == "X-CACHECONF:"
+    }
+}
+
+/// A parsed ccache file (file format versions 3 and 4)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Ccache {
+    pub version: u16,
+    pub default_principal: CcachePrincipal,
+    pub credentials: Vec<CcacheCredential>,
+}
+
+impl Ccache {
+    /// The TGT of the default principal's realm, else the first TGT
+    pub fn tgt(&self) -> Option<&CcacheCredential> {
+        let tgts = || self.credentials.iter().filter(|c| c.is_tgt());
+        tgts()
+            .find(|c| c.server.components.get(1) == Some(&self.default_principal.realm))
+            .or_else(|| tgts().next())
+    }
+}
+
+/// Big-endian reader over a ccache file
+struct CcacheReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> CcacheReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.pos < len {
+            return Err(format!("Truncated ccache at offset {}", self.pos));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(
+            self.take(2)?.try_into().unwrap_or_default(),
+        ))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(
+            self.take(4)?.try_into().unwrap_or_default(),
+        ))
+    }
+
+    fn octets(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.octets()?).into_owned())
+    }
+
+    fn principal(&mut self) -> Result<CcachePrincipal, String> {
+        let name_type = self.u32()?;
+        let count = self.u32()?;
+        let realm = self.string()?;
+        let components = (0..count)
+            .map(|_| self.string())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CcachePrincipal {
+            name_type,
+            realm,
+            components,
+        })
+    }
+
+    fn credential(&mut self, version: u16) -> Result<CcacheCredential, String> {
+        let client = self.principal()?;
+        let server = self.principal()?;
+        let enctype = self.u16()?;
+        if version == 0x0503 {
+            self.u16()?;
+        }
+        self.octets()?;
+        let auth_time = self.u32()? as i64;
+        let start_time = self.u32()? as i64;
+        let end_time = self.u32()? as i64;
+        let renew_till = self.u32()? as i64;
+        let is_skey = self.u8()? != 0;
+        let flags = self.u32()?;
+        // addresses, authdata, ticket, second ticket
+        for _ in 0..2 {
+            let count = self.u32()?;
+            for _ in 0..count {
+                self.u16()?;
+                self.octets()?;
+            }
+        }
+        self.octets()?;
+        self.octets()?;
+        Ok(CcacheCredential {
+            client,
+            server,
+            enctype,
+            // MIT leaves starttime at 0 when it equals authtime
+            start_time: if start_time == 0 {
+                auth_time
+            } else {
+                start_time
+            },
+            auth_time,
+            end_time,
+            renew_till,
+            is_skey,
+            flags,
+        })
+    }
+}
+
+/// Parse a FILE: ccache
+pub fn parse_ccache(data: &[u8]) -> Result<Ccache, String> {
+    let mut reader = CcacheReader { data, pos: 0 };
+    let version = reader.u16()?;
+    match version {
+        0x0504 => {
+            let header_len = reader.u16()? as usize;
+            reader.take(header_len)?;
+        }
+        0x0503 => {}
+        _ => return Err(format!("Unsupported ccache version {:#06x}", version)),
+    }
+    let default_principal = reader.principal()?;
+    let mut credentials = vec![];
+    while reader.pos < data.len() {
+        credentials.push(reader.credential(version)?);
+    }
+    Ok(Ccache {
+        version,
+        default_principal,
+        credentials,
+    })
+}
+
+pub fn read_ccache(path: &Path) -> Result<Ccache, String> {
+    let data =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_ccache(&data).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Connection ids that have a cache file
+pub fn cached_connection_ids() -> Vec<i64> {
+    let Ok(entries) = std::fs::read_dir(ccache_dir()) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("krb5cc_"))
+                .and_then(|id| id.parse().ok())
+        })
+        .collect()
+}
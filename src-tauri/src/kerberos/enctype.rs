@@ -0,0 +1,29 @@
+/// Name of a Kerberos encryption type as printed by `klist -e`
+pub fn enctype_name(enctype: i32) -> String {
+    match enctype {
+        1 => "des-cbc-crc",
+        3 => "des-cbc-md5",
+        16 => "des3-cbc-sha1",
+        17 => "aes128-cts-hmac-sha1-96",
+        18 => "aes256-cts-hmac-sha1-96",
+        19 => "aes128-cts-hmac-sha256-128",
+        20 => "aes256-cts-hmac-sha384-192",
+        23 => "arcfour-hmac",
+        24 => "arcfour-hmac-exp",
+        25 => "camellia128-cts-cmac",
+        26 => "camellia256-cts-cmac",
+        _ => return format!("enctype {}", enctype),
+    }
+    .to_string()
+}
+
+/// Rank of the enctypes the Kerberos client can use, higher is stronger;
+/// `None` for enctypes it cannot use
+pub fn supported_enctype_strength(enctype: i32) -> Option<u8> {
+    match enctype {
+        18 => Some(3),
+        17 => Some(2),
+        23 => Some(1),
+        _ => None,
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+
+use awol2005ex_kerberos_crypto::Key;
+use awol2005ex_kerberos_keytab::Keytab;
+use serde::{Deserialize, Serialize};
+
+use super::enctype::{enctype_name, supported_enctype_strength};
+
+/// One keytab entry, without its key
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct KeytabEntry {
+    pub principal: String,
+    pub kvno: u32,
+    pub enctype: i32,
+    pub enctype_name: String,
+    /// Unix seconds when the key was written
+    pub timestamp: i64,
+    pub supported: bool,
+}
+
+/// Parse a keytab file into its entries and their raw keys
+fn read_keytab(keytab_path: &str) -> Result<Vec<(KeytabEntry, Vec<u8>)>, String> {
+    let keytab_data =
+        fs::read(keytab_path).map_err(|e| format!("Failed to read {}: {}", keytab_path, e))?;
+    let keytab = Keytab::parse(&keytab_data)
+        .map_err(|e| format!("Failed to parse keytab: {:?}", e))?
+        .1;
+
+    let mut entries = vec![];
+    for entry in keytab.entries {
+        let realm_str: String = entry
+            .realm
+            .try_into()
+            .map_err(|e| format!("Invalid realm in keytab: {:?}", e))?;
+        let components: Vec<String> = entry
+            .components
+            .into_iter()
+            .map(|c| String::from_utf8(c.data))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid principal in keytab: {}", e))?;
+        let enctype = entry.key.keytype as i32;
+        // the 32-bit kvno, when present and non-zero, supersedes the 8-bit one
+        let kvno = entry
+            .vno
+            .filter(|vno| *vno != 0)
+            .unwrap_or(entry.vno8 as u32);
+        entries.push((
+            KeytabEntry {
+                principal: format!("{}@{}", components.join("/"), realm_str),
+                kvno,
+                enctype,
+                enctype_name: enctype_name(enctype),
+                timestamp: entry.timestamp as i64,
+                supported: supported_enctype_strength(enctype).is_some(),
+            },
+            entry.key.keyvalue,
+        ));
+    }
+    Ok(entries)
+}
+
+/// List every entry of a keytab
+pub fn list_keytab(keytab_path: &str) -> Result<Vec<KeytabEntry>, String> {
+    Ok(read_keytab(keytab_path)?
+        .into_iter()
+        .map(|(entry, _)| entry)
+        .collect())
+}
+
+/// The entry used to log in as `principal`: the highest kvno, then the
+/// strongest supported enctype of that kvno
+pub fn pick_entry<'a>(entries: &'a [KeytabEntry], principal: &str) -> Option<&'a KeytabEntry> {
+    entries
+        .iter()
+        .filter(|e| e.principal == principal)
+        .filter_map(|e| supported_enctype_strength(e.enctype).map(|strength| (e, strength)))
+        .max_by_key(|(e, strength)| (e.kvno, *strength))
+        .map(|(e, _)| e)
+}
+
+/// Principals of a keytab in file order, each with the entry `pick_entry` chooses
+pub fn keytab_principals(entries: &[KeytabEntry]) -> Vec<KeytabEntry> {
+    let mut principals: Vec<&str> = vec![];
+    for entry in entries.iter() {
+        if !principals.contains(&entry.principal.as_str()) {
+            principals.push(&entry.principal);
+        }
+    }
+    principals
+        .into_iter()
+        .filter_map(|principal| pick_entry(entries, principal).cloned())
+        .collect()
+}
+
+/// Load the key of `principal` chosen by `pick_entry`
+pub fn load_key_from_keytab(keytab_path: &str, principal: &str) -> Result<Key, String> {
+    let entries = read_keytab(keytab_path)?;
+    let infos: Vec<KeytabEntry> = entries.iter().map(|(entry, _)| entry.clone()).collect();
+    let Some(picked) = pick_entry(&infos, principal) else {
+        return Err(match infos.iter().find(|e| e.principal == principal) {
+            Some(_) => format!(
+                "Keytab has no supported enctype for {}, supported: aes256-cts-hmac-sha1-96, aes128-cts-hmac-sha1-96, arcfour-hmac",
+                principal
+            ),
+            None => format!("Principal {} not found in keytab", principal),
+        });
+    };
+    log::info!(
+        "Using keytab entry {} kvno {} {}",
+        picked.principal,
+        picked.kvno,
+        picked.enctype_name
+    );
+    let (_, key_value) = entries
+        .iter()
+        .find(|(entry, _)| {
+            entry.principal == picked.principal
+                && entry.kvno == picked.kvno
+                && entry.enctype == picked.enctype
+        })
+        .ok_or_else(|| format!("Principal {} not found in keytab", principal))?;
+
+    // Convert keyvalue to hex string
+    let key_hex = hex::encode(key_value);
+    let key = match picked.enctype {
+        23 => Key::from_rc4_key_string(&key_hex),     // RC4-HMAC
+        17 => Key::from_aes_128_key_string(&key_hex), // AES128
+        18 => Key::from_aes_256_key_string(&key_hex), // AES256
+        _ => return Err(format!("Unsupported key type: {}", picked.enctype)),
+    };
+    key.map_err(|e| e.to_string())
+}
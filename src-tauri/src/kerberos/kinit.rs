@@ -0,0 +1,211 @@
+use std::net::IpAddr;
+
+use ascii::AsciiString;
+use awol2005ex_kerbeiros::{Credential, TgtRequester, TransportProtocol};
+use awol2005ex_kerberos_crypto::Key;
+
+use super::keytab::load_key_from_keytab;
+use super::krb5_conf::{DEFAULT_KDC_PORT, KdcAddress, Krb5Conf};
+
+/// Split a principal into `(name, realm)`. Without an `@REALM` part the realm
+/// comes from `[domain_realm]` for `service/host` principals, else `default_realm`.
+pub fn split_principal(principal: &str, conf: &Krb5Conf) -> Result<(String, String), String> {
+    match principal.rsplit_once('@') {
+        Some((name, realm)) if !name.is_empty() && !realm.is_empty() => {
+            Ok((name.to_string(), realm.to_string()))
+        }
+        Some(_) => Err(format!("Invalid principal: {}", principal)),
+        None => match principal
+            .split_once('/')
+            .and_then(|(_, host)| conf.realm_for_host(host))
+            .or_else(|| conf.default_realm.clone())
+        {
+            Some(realm) => Ok((principal.to_string(), realm)),
+            None => Err(format!(
+                "Principal {} has no realm and krb5.conf has no default_realm",
+                principal
+            )),
+        },
+    }
+}
+
+/// Perform kinit using keytab file
+pub async fn kinit_with_keytab(
+    conf: &Krb5Conf,
+    keytab_path: &str,
+    principal: &str,
+) -> Result<Credential, String> {
+    log::info!("Performing kinit with keytab...");
+    log::info!("Keytab: {}", keytab_path);
+    log::info!("Principal: {}", principal);
+
+    // Load the key from keytab
+    let user_key = load_key_from_keytab(keytab_path, principal)?;
+
+    request_tgt(conf, principal, user_key).await
+}
+
+/// Perform kinit using password. The key is derived from the password and the
+/// salt announced by the KDC in its pre-authentication reply.
+pub async fn kinit_with_password(
+    conf: &Krb5Conf,
+    password: &str,
+    principal: &str,
+) -> Result<Credential, String> {
+    log::info!("Performing kinit with password...");
+    log::info!("Principal: {}", principal);
+
+    let user_key = Key::Secret(password.to_string());
+
+    request_tgt(conf, principal, user_key).await
+}
+
+/// KRB-ERROR codes meaning the principal or its key is wrong. Every KDC of the
+/// realm answers the same, so these stop the failover and a wrong password is
+/// not replayed against every KDC.
+const PRINCIPAL_ERROR_CODES: [(i32, &str); 5] = [
+    (6, "KDC_ERR_C_PRINCIPAL_UNKNOWN"),
+    (18, "KDC_ERR_CLIENT_REVOKED"),
+    (23, "KDC_ERR_KEY_EXPIRED"),
+    (24, "KDC_ERR_PREAUTH_FAILED"),
+    (31, "KRB_AP_ERR_BAD_INTEGRITY"),
+];
+
+/// The reply does not fit in a UDP datagram, the request is repeated over TCP
+const KRB_ERR_RESPONSE_TOO_BIG: i32 = 52;
+
+/// Error code of a KRB-ERROR reply, taken from the requester's error such as
+/// `KrbError { .., error_code: 24, .. }`
+fn krb_error_code(debug: &str) -> Option<i32> {
+    let (_, rest) = debug.split_once("error_code:")?;
+    let value = rest.split([',', '}']).next()?;
+    let value = value.trim().trim_end_matches(')');
+    let digits = value.trim_start_matches(|c: char| !c.is_ascii_digit() && c != '-');
+    let digits = digits.rsplit('(').next()?;
+    digits.parse().ok()
+}
+
+/// Request a TGT, trying every KDC of the realm in configuration order. Each
+/// KDC is tried over UDP then TCP (TCP only when `udp_preference_limit = 1`).
+/// An attempt covers both AS exchanges, so it gets twice `kdc_timeout`.
+/// Only principal and password errors stop the failover, see
+/// `PRINCIPAL_ERROR_CODES`; other KRB-ERROR replies move on to the next KDC.
+pub async fn request_tgt(
+    conf: &Krb5Conf,
+    principal: &str,
+    user_key: Key,
+) -> Result<Credential, String> {
+    let (name, realm) = split_principal(principal, conf)?;
+    let kdcs = conf.kdcs(&realm)?;
+
+    let realm_ascii =
+        AsciiString::from_ascii(realm.as_str()).map_err(|e| format!("Invalid realm: {}", e))?;
+    let username_ascii =
+        AsciiString::from_ascii(name.as_str()).map_err(|e| format!("Invalid username: {}", e))?;
+
+    let protocols = if conf.prefer_udp() {
+        vec![TransportProtocol::UDP, TransportProtocol::TCP]
+    } else {
+        vec![TransportProtocol::TCP]
+    };
+    let attempt_timeout = conf.kdc_timeout * 2;
+
+    let mut errors = vec![];
+    for kdc in kdcs.iter() {
+        // The requester always talks to port 88, other ports are rejected
+        // when the connection is saved
+        if kdc.port != DEFAULT_KDC_PORT {
+            errors.push(format!(
+                "{}: only port {} is supported",
+                kdc, DEFAULT_KDC_PORT
+            ));
+            continue;
+        }
+        let kdc_address = match resolve_kdc(kdc).await {
+            Ok(ip) => ip,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        for protocol in protocols.iter() {
+            log::info!(
+                "Requesting TGT for {} from {} ({}) over {:?}",
+                principal,
+                kdc,
+                kdc_address,
+                protocol
+            );
+            let mut tgt_requester = TgtRequester::new(realm_ascii.clone(), kdc_address);
+            tgt_requester.set_transport_protocol(protocol.clone());
+            let username = username_ascii.clone();
+            let key = user_key.clone();
+            let request = tokio::task::spawn_blocking(move || {
+                tgt_requester
+                    .request(&username, Some(&key))
+                    .map_err(|e| (format!("{}", e), format!("{:?}", e)))
+            });
+            match tokio::time::timeout(attempt_timeout, request).await {
+                Ok(Ok(Ok(credential))) => {
+                    log::info!("Successfully obtained TGT for {} from {}", principal, kdc);
+                    return Ok(credential);
+                }
+                Ok(Ok(Err((message, debug)))) => {
+                    log::warn!(
+                        "TGT request to {} over {:?} failed: {}",
+                        kdc,
+                        protocol,
+                        message
+                    );
+                    let code = krb_error_code(&debug);
+                    if let Some((_, name)) =
+                        PRINCIPAL_ERROR_CODES.iter().find(|(c, _)| Some(*c) == code)
+                    {
+                        return Err(format!(
+                            "TGT request failed ({}, {}): {}",
+                            kdc, name, message
+                        ));
+                    }
+                    errors.push(format!("{} {:?}: {}", kdc, protocol, message));
+                    match code {
+                        Some(KRB_ERR_RESPONSE_TOO_BIG) => {
+                            log::info!("Reply of {} is too big for UDP, retrying over TCP", kdc)
+                        }
+                        // The KDC answered, another protocol gets the same reply
+                        Some(_) => break,
+                        None => {}
+                    }
+                }
+                Ok(Err(e)) => errors.push(format!("{} {:?}: {}", kdc, protocol, e)),
+                Err(_) => {
+                    log::warn!("TGT request to {} over {:?} timed out", kdc, protocol);
+                    errors.push(format!(
+                        "{} {:?}: timed out after {}ms",
+                        kdc,
+                        protocol,
+                        attempt_timeout.as_millis()
+                    ));
+                }
+            }
+        }
+    }
+
+    Err(format!(
+        "No KDC of realm {} could be reached: {}",
+        realm,
+        errors.join("; ")
+    ))
+}
+
+/// Resolve a KDC host, which can be an IP address or a hostname
+async fn resolve_kdc(kdc: &KdcAddress) -> Result<IpAddr, String> {
+    if let Ok(ip) = kdc.host.parse::<IpAddr>() {
+        return Ok(ip);
+    }
+    tokio::net::lookup_host((kdc.host.as_str(), kdc.port))
+        .await
+        .map_err(|e| format!("Could not resolve KDC hostname '{}': {}", kdc.host, e))?
+        .next()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| format!("Could not resolve KDC hostname: {}", kdc.host))
+}
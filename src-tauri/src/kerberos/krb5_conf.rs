@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Default KDC port
+pub const DEFAULT_KDC_PORT: u16 = 88;
+/// MIT's default `kdc_timeout`
+pub const DEFAULT_KDC_TIMEOUT: Duration = Duration::from_secs(1);
+/// MIT's default `udp_preference_limit`
+pub const DEFAULT_UDP_PREFERENCE_LIMIT: usize = 1465;
+/// Guards against `include` loops
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// A KDC entry of a realm, `kdc = host[:port]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KdcAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl std::fmt::Display for KdcAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// The `[realms]` entry of a realm
+#[derive(Debug, Clone, Default)]
+pub struct RealmConf {
+    pub kdcs: Vec<KdcAddress>,
+}
+
+/// The parts of krb5.conf used by hdfs-gui
+#[derive(Debug, Clone)]
+pub struct Krb5Conf {
+    /// Files that were read, in order
+    pub files: Vec<PathBuf>,
+    pub default_realm: Option<String>,
+    pub kdc_timeout: Duration,
+    pub udp_preference_limit: usize,
+    pub realms: HashMap<String, RealmConf>,
+    /// `[domain_realm]` mappings in file order, `.example.com` matches subdomains
+    pub domain_realm: Vec<(String, String)>,
+}
+
+impl Default for Krb5Conf {
+    fn default() -> Self {
+        Krb5Conf {
+            files: vec![],
+            default_realm: None,
+            kdc_timeout: DEFAULT_KDC_TIMEOUT,
+            udp_preference_limit: DEFAULT_UDP_PREFERENCE_LIMIT,
+            realms: HashMap::new(),
+            domain_realm: vec![],
+        }
+    }
+}
+
+/// krb5.conf locations to try: the file stored with the connection, then
+/// `KRB5_CONFIG` (a `:`/`;` separated list), then the platform defaults.
+pub fn krb5_conf_candidates(krb5_conf: Option<&str>) -> Vec<PathBuf> {
+    if let Some(krb5_conf) = krb5_conf {
+        return vec![PathBuf::from(krb5_conf)];
+    }
+    if let Ok(krb5_config) = env::var("KRB5_CONFIG") {
+        let separator = if cfg!(target_os = "windows") {
+            ';'
+        } else {
+            ':'
+        };
+        return krb5_config
+            .split(separator)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .collect();
+    }
+    vec![
+        PathBuf::from("/etc/krb5.conf"),
+        PathBuf::from("/etc/krb5/krb5.conf"),
+        PathBuf::from("C:\\Windows\\krb5.ini"),
+        PathBuf::from(format!(
+            "{}\\krb5.ini",
+            env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string())
+        )),
+    ]
+}
+
+/// Parse a krb5.conf duration: `30` and `30s` are seconds, also `500ms`,
+/// `2m`, `1h`, `1d` or `h:m:s`.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.contains(':') {
+        let mut secs = 0u64;
+        for part in value.split(':') {
+            secs = secs * 60 + part.trim().parse::<u64>().ok()?;
+        }
+        return Some(Duration::from_secs(secs));
+    }
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => (&value[..i], value[i..].trim()),
+        None => (value, ""),
+    };
+    let number = number.parse::<u64>().ok()?;
+    match unit {
+        "" | "s" => Some(Duration::from_secs(number)),
+        "ms" => Some(Duration::from_millis(number)),
+        "m" => Some(Duration::from_secs(number * 60)),
+        "h" => Some(Duration::from_secs(number * 3600)),
+        "d" => Some(Duration::from_secs(number * 86400)),
+        _ => None,
+    }
+}
+
+/// Parse `host`, `host:port`, `[v6]:port` or `tcp/host:port`
+pub fn parse_kdc_address(value: &str) -> Option<KdcAddress> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("tcp/")
+        .or_else(|| value.strip_prefix("udp/"))
+        .unwrap_or(value);
+    if value.is_empty() {
+        return None;
+    }
+    if let Some(rest) = value.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None => DEFAULT_KDC_PORT,
+        };
+        return Some(KdcAddress {
+            host: host.to_string(),
+            port,
+        });
+    }
+    match value.rsplit_once(':') {
+        //ipv6地址没有[]时不含端口
+        Some((host, port)) if !host.contains(':') => Some(KdcAddress {
+            host: host.to_string(),
+            port: port.parse().ok()?,
+        }),
+        _ => Some(KdcAddress {
+            host: value.to_string(),
+            port: DEFAULT_KDC_PORT,
+        }),
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"').trim_matches('\'')
+}
+
+impl Krb5Conf {
+    /// Load the first existing file of `krb5_conf_candidates`
+    pub fn load(krb5_conf: Option<&str>) -> Result<Krb5Conf, String> {
+        let candidates = krb5_conf_candidates(krb5_conf);
+        for path in candidates.iter() {
+            if path.is_file() {
+                let mut conf = Krb5Conf::default();
+                conf.read_file(path, 0)?;
+                return Ok(conf);
+            }
+        }
+        Err(format!(
+            "No krb5.conf found, tried: {}",
+            candidates
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    fn read_file(&mut self, path: &Path, depth: usize) -> Result<(), String> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(format!("Too many nested includes at {}", path.display()));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        self.files.push(path.to_path_buf());
+        self.parse_content(&content, Some(path), depth);
+        Ok(())
+    }
+
+    fn include(&mut self, path: &Path, depth: usize) {
+        if let Err(e) = self.read_file(path, depth + 1) {
+            log::warn!("krb5.conf include ignored: {}", e);
+        }
+    }
+
+    fn include_dir(&mut self, dir: &Path, depth: usize) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            log::warn!("krb5.conf includedir not readable: {}", dir.display());
+            return;
+        };
+        //与MIT一致:只读取由字母数字、-、_组成或以.conf结尾的文件,按文件名排序
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .filter(|p| {
+                let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                name.ends_with(".conf")
+                    || name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+            .collect();
+        files.sort();
+        for file in files {
+            self.include(&file, depth);
+        }
+    }
+
+    fn parse_content(&mut self, content: &str, path: Option<&Path>, depth: usize) {
+        let base_dir = path.and_then(|p| p.parent()).map(|p| p.to_path_buf());
+        let mut section = String::new();
+        //[realms]中当前的realm和嵌套层级
+        let mut current_realm: Option<String> = None;
+        let mut nesting = 0usize;
+
+        for raw_line in content.lines() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if nesting == 0 {
+                if let Some(file) = line.strip_prefix("includedir ") {
+                    if path.is_some() {
+                        self.include_dir(&resolve(&base_dir, file.trim()), depth);
+                    }
+                    continue;
+                }
+                if let Some(file) = line.strip_prefix("include ") {
+                    if path.is_some() {
+                        self.include(&resolve(&base_dir, file.trim()), depth);
+                    }
+                    continue;
+                }
+                if line.starts_with('[') {
+                    if let Some(end) = line.find(']') {
+                        section = line[1..end].trim().to_string();
+                    }
+                    continue;
+                }
+            }
+
+            if line.starts_with('}') {
+                nesting = nesting.saturating_sub(1);
+                if nesting == 0 {
+                    current_realm = None;
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            let value = value.trim();
+            if value.starts_with('{') {
+                nesting += 1;
+                if nesting == 1 && section == "realms" {
+                    let realm = key.to_string();
+                    self.realms.entry(realm.clone()).or_default();
+                    current_realm = Some(realm);
+                }
+                continue;
+            }
+            let value = unquote(value);
+
+            match section.as_str() {
+                "libdefaults" if nesting == 0 => match key {
+                    "default_realm" => self.default_realm = Some(value.to_string()),
+                    "kdc_timeout" => {
+                        if let Some(timeout) = parse_duration(value) {
+                            self.kdc_timeout = timeout;
+                        }
+                    }
+                    "udp_preference_limit" => {
+                        if let Ok(limit) = value.parse() {
+                            self.udp_preference_limit = limit;
+                        }
+                    }
+                    _ => {}
+                },
+                "realms" if nesting == 1 => {
+                    let Some(realm) = current_realm.as_ref() else {
+                        continue;
+                    };
+                    let realm_conf = self.realms.entry(realm.clone()).or_default();
+                    if key != "kdc" {
+                        continue;
+                    }
+                    if let Some(kdc) =
+                        parse_kdc_address(value).filter(|kdc| !realm_conf.kdcs.contains(kdc))
+                    {
+                        realm_conf.kdcs.push(kdc);
+                    }
+                }
+                "domain_realm" if nesting == 0 => {
+                    self.domain_realm
+                        .push((key.to_lowercase(), value.to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// KDCs of a realm in configuration order
+    pub fn kdcs(&self, realm: &str) -> Result<Vec<KdcAddress>, String> {
+        match self.realms.get(realm) {
+            Some(realm_conf) if !realm_conf.kdcs.is_empty() => Ok(realm_conf.kdcs.clone()),
+            _ => Err(format!(
+                "No kdc configured for realm {} in {}",
+                realm,
+                self.files
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Realm of a host from `[domain_realm]`: an exact host entry wins over
+    /// the longest matching `.domain` entry, falling back to `default_realm`.
+    pub fn realm_for_host(&self, host: &str) -> Option<String> {
+        let host = host.to_lowercase();
+        if let Some((_, realm)) = self.domain_realm.iter().find(|(d, _)| *d == host) {
+            return Some(realm.clone());
+        }
+        self.domain_realm
+            .iter()
+            .filter(|(d, _)| d.starts_with('.') && (host.ends_with(d.as_str()) || host == d[1..]))
+            .max_by_key(|(d, _)| d.len())
+            .map(|(_, realm)| realm.clone())
+            .or_else(|| self.default_realm.clone())
+    }
+
+    /// Whether UDP should be tried before TCP (`udp_preference_limit = 1` forces TCP)
+    pub fn prefer_udp(&self) -> bool {
+        self.udp_preference_limit > 1
+    }
+}
+
+fn resolve(base_dir: &Option<PathBuf>, file: &str) -> PathBuf {
+    let path = PathBuf::from(file);
+    match base_dir {
+        Some(base_dir) if path.is_relative() => base_dir.join(path),
+        _ => path,
+    }
+}
@@ -0,0 +1,12 @@
+//krb5.conf解析
+pub mod krb5_conf;
+//kinit,向KDC申请TGT
+pub mod kinit;
+//每个连接独立的票据缓存
+pub mod ccache;
+//加密类型
+pub mod enctype;
+//keytab解析
+pub mod keytab;
+//SPNEGO(HTTP Negotiate)认证,用于WebHDFS
+pub mod spnego;
@@ -0,0 +1,273 @@
+use std::ffi::{CString, OsStr, c_char, c_void};
+use std::ptr;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use libloading::Library;
+use once_cell::sync::Lazy;
+
+type OmUint32 = u32;
+
+#[repr(C)]
+struct GssOidDesc {
+    length: OmUint32,
+    elements: *const c_void,
+}
+
+#[repr(C)]
+struct GssBufferDesc {
+    length: usize,
+    value: *mut c_void,
+}
+
+type GssImportName = unsafe extern "C" fn(
+    *mut OmUint32,
+    *const GssBufferDesc,
+    *const GssOidDesc,
+    *mut *mut c_void,
+) -> OmUint32;
+type GssInitSecContext = unsafe extern "C" fn(
+    *mut OmUint32,
+    *const c_void,
+    *mut *mut c_void,
+    *mut c_void,
+    *const GssOidDesc,
+    OmUint32,
+    OmUint32,
+    *const c_void,
+    *const GssBufferDesc,
+    *mut *const GssOidDesc,
+    *mut GssBufferDesc,
+    *mut OmUint32,
+    *mut OmUint32,
+) -> OmUint32;
+type GssReleaseBuffer = unsafe extern "C" fn(*mut OmUint32, *mut GssBufferDesc) -> OmUint32;
+type GssReleaseName = unsafe extern "C" fn(*mut OmUint32, *mut *mut c_void) -> OmUint32;
+type GssDeleteSecContext =
+    unsafe extern "C" fn(*mut OmUint32, *mut *mut c_void, *mut GssBufferDesc) -> OmUint32;
+type GssKrb5CcacheName =
+    unsafe extern "C" fn(*mut OmUint32, *const c_char, *mut *const c_char) -> OmUint32;
+type GssDisplayStatus = unsafe extern "C" fn(
+    *mut OmUint32,
+    OmUint32,
+    i32,
+    *const GssOidDesc,
+    *mut OmUint32,
+    *mut GssBufferDesc,
+) -> OmUint32;
+
+/// GSS_C_NT_HOSTBASED_SERVICE, 1.2.840.113554.1.2.1.4
+static NT_HOSTBASED_SERVICE: [u8; 10] =
+    [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x12, 0x01, 0x02, 0x01, 0x04];
+/// The SPNEGO mechanism, 1.3.6.1.5.5.2
+static SPNEGO_MECHANISM: [u8; 6] = [0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+
+const GSS_S_COMPLETE: OmUint32 = 0;
+const GSS_S_CONTINUE_NEEDED: OmUint32 = 1;
+const GSS_C_GSS_CODE: i32 = 1;
+const GSS_C_MECH_CODE: i32 = 2;
+
+/// The same GSSAPI libraries hdfs-native loads for kerberized RPC
+#[cfg(target_os = "macos")]
+static GSSAPI_LIBRARIES: &[&str] = &[
+    "libgssapi_krb5.2.2.dylib",
+    "libgssapi_krb5.dylib",
+    "/System/Library/Frameworks/GSS.framework/GSS",
+];
+#[cfg(target_os = "windows")]
+static GSSAPI_LIBRARIES: &[&str] = &["gssapi64.dll", "gssapi32.dll"];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+static GSSAPI_LIBRARIES: &[&str] = &["libgssapi_krb5.so.2", "libgssapi_krb5.so"];
+
+static GSSAPI: Lazy<Result<Library, String>> = Lazy::new(|| {
+    let mut errors = vec![];
+    for name in GSSAPI_LIBRARIES {
+        // Safety: loading the system GSSAPI library runs no initialisers we depend on
+        match unsafe { Library::new(name) } {
+            Ok(library) => return Ok(library),
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+    Err(format!(
+        "No GSSAPI library found, install MIT Kerberos ({})",
+        errors.join("; ")
+    ))
+});
+
+fn symbol<T: Copy>(library: &Library, name: &[u8]) -> Result<T, String> {
+    // Safety: every symbol is looked up with the signature of gssapi.h
+    unsafe { library.get::<T>(name) }
+        .map(|s| *s)
+        .map_err(|e| format!("GSSAPI symbol {}: {}", String::from_utf8_lossy(name), e))
+}
+
+/// Take the bytes out of a GSSAPI buffer and release it
+fn take_buffer(release: GssReleaseBuffer, buffer: &mut GssBufferDesc) -> Vec<u8> {
+    let bytes = if buffer.value.is_null() || buffer.length == 0 {
+        vec![]
+    } else {
+        // Safety: GSSAPI filled `length` bytes at `value`
+        unsafe { std::slice::from_raw_parts(buffer.value as *const u8, buffer.length) }.to_vec()
+    };
+    let mut minor = 0;
+    // Safety: the buffer was allocated by GSSAPI
+    unsafe { release(&mut minor, buffer) };
+    bytes
+}
+
+/// Human-readable text of a major/minor status pair
+fn status_message(library: &Library, major: OmUint32, minor: OmUint32) -> String {
+    let (Ok(display), Ok(release)) = (
+        symbol::<GssDisplayStatus>(library, b"gss_display_status\0"),
+        symbol::<GssReleaseBuffer>(library, b"gss_release_buffer\0"),
+    ) else {
+        return format!("major {:#x}, minor {:#x}", major, minor);
+    };
+    let mut messages = vec![];
+    for (code, kind) in [(major, GSS_C_GSS_CODE), (minor, GSS_C_MECH_CODE)] {
+        if code == 0 {
+            continue;
+        }
+        let mut context = 0;
+        loop {
+            let mut minor_status = 0;
+            let mut buffer = GssBufferDesc {
+                length: 0,
+                value: ptr::null_mut(),
+            };
+            // Safety: all pointers are valid for the duration of the call
+            let result = unsafe {
+                display(
+                    &mut minor_status,
+                    code,
+                    kind,
+                    ptr::null(),
+                    &mut context,
+                    &mut buffer,
+                )
+            };
+            if result != GSS_S_COMPLETE {
+                break;
+            }
+            let message = take_buffer(release, &mut buffer);
+            messages.push(
+                String::from_utf8_lossy(&message)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            );
+            if context == 0 {
+                break;
+            }
+        }
+    }
+    messages.join(": ")
+}
+
+/// Build the `Authorization: Negotiate` token for `HTTP@<host>`.
+///
+/// `ccache` selects the credential cache (e.g. `FILE:/path`) for this call only;
+/// `None` uses the GSSAPI default. This blocks, so call it from `spawn_blocking`.
+pub fn negotiate_token(host: &str, ccache: Option<&OsStr>) -> Result<String, String> {
+    let _env = super::ccache::ENV_LOCK
+        .read()
+        .unwrap_or_else(|e| e.into_inner());
+    let library = GSSAPI.as_ref().map_err(|e| e.clone())?;
+    let import_name = symbol::<GssImportName>(library, b"gss_import_name\0")?;
+    let init_sec_context = symbol::<GssInitSecContext>(library, b"gss_init_sec_context\0")?;
+    let release_buffer = symbol::<GssReleaseBuffer>(library, b"gss_release_buffer\0")?;
+    let release_name = symbol::<GssReleaseName>(library, b"gss_release_name\0")?;
+    let delete_sec_context = symbol::<GssDeleteSecContext>(library, b"gss_delete_sec_context\0")?;
+
+    // Blocking threads are reused, so the default ccache is selected explicitly too
+    let ccache_name = symbol::<GssKrb5CcacheName>(library, b"gss_krb5_ccache_name\0")?;
+    let ccache = ccache
+        .map(|c| CString::new(c.to_string_lossy().as_bytes()))
+        .transpose()
+        .map_err(|e| format!("Invalid ccache name: {}", e))?;
+    let mut minor = 0;
+    // Safety: the name is copied by GSSAPI and only affects the calling thread
+    let major = unsafe {
+        ccache_name(
+            &mut minor,
+            ccache.as_ref().map_or(ptr::null(), |c| c.as_ptr()),
+            ptr::null_mut(),
+        )
+    };
+    if major != GSS_S_COMPLETE {
+        return Err(format!(
+            "Failed to select ccache: {}",
+            status_message(library, major, minor)
+        ));
+    }
+
+    let service = format!("HTTP@{}", host);
+    let name_buffer = GssBufferDesc {
+        length: service.len(),
+        value: service.as_ptr() as *mut c_void,
+    };
+    let name_type = GssOidDesc {
+        length: NT_HOSTBASED_SERVICE.len() as OmUint32,
+        elements: NT_HOSTBASED_SERVICE.as_ptr() as *const c_void,
+    };
+    let mechanism = GssOidDesc {
+        length: SPNEGO_MECHANISM.len() as OmUint32,
+        elements: SPNEGO_MECHANISM.as_ptr() as *const c_void,
+    };
+
+    let mut minor = 0;
+    let mut target: *mut c_void = ptr::null_mut();
+    // Safety: the buffers outlive the call and `target` is released below
+    let major = unsafe { import_name(&mut minor, &name_buffer, &name_type, &mut target) };
+    if major != GSS_S_COMPLETE {
+        return Err(format!(
+            "Failed to import {}: {}",
+            service,
+            status_message(library, major, minor)
+        ));
+    }
+
+    let mut context: *mut c_void = ptr::null_mut();
+    let mut output = GssBufferDesc {
+        length: 0,
+        value: ptr::null_mut(),
+    };
+    // Safety: default credentials, no channel bindings and no input token
+    let major = unsafe {
+        init_sec_context(
+            &mut minor,
+            ptr::null(),
+            &mut context,
+            target,
+            &mechanism,
+            0,
+            0,
+            ptr::null(),
+            ptr::null(),
+            ptr::null_mut(),
+            &mut output,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    let token = take_buffer(release_buffer, &mut output);
+    let mut cleanup_minor = 0;
+    // Safety: both handles were created by GSSAPI above
+    unsafe {
+        if !context.is_null() {
+            delete_sec_context(&mut cleanup_minor, &mut context, ptr::null_mut());
+        }
+        release_name(&mut cleanup_minor, &mut target);
+    }
+
+    if major != GSS_S_COMPLETE && major != GSS_S_CONTINUE_NEEDED {
+        return Err(format!(
+            "SPNEGO for {} failed: {}",
+            service,
+            status_message(library, major, minor)
+        ));
+    }
+    if token.is_empty() {
+        return Err(format!("SPNEGO for {} produced no token", service));
+    }
+    Ok(STANDARD.encode(token))
+}
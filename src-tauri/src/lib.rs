@@ -1,14 +1,26 @@
 use commands::{
-    hdfs_acls::*, hdfs_avro::*, hdfs_config::*, hdfs_file::*, hdfs_orc::*, hdfs_parquet::*,
+    hdfs_acls::*, hdfs_avro::*, hdfs_cluster::*, hdfs_config::*,
+    hdfs_config_bundle::*, hdfs_config_secret::*, hdfs_config_xml::*,
+    hdfs_connection::*, hdfs_file::*, hdfs_kerberos::*, hdfs_orc::*,
+    hdfs_parquet::*, hdfs_transfer::*, hdfs_transfer_queue::*,
 };
 
+mod backend;
 mod commands;
 mod db;
+mod kerberos;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let current_dir = std::env::current_dir().map_err(|e| e.to_string()).unwrap();
+    //指定数据目录时日志也写到该目录,否则使用系统的app-log目录
+    let log_target = match db::data_dir::data_dir_override() {
+        Some(data_dir) => tauri_plugin_log::TargetKind::Folder {
+            path: data_dir.join("logs"),
+            file_name: None,
+        },
+        None => tauri_plugin_log::TargetKind::LogDir { file_name: None },
+    };
     tauri::Builder::default()
         .plugin(
             tauri_plugin_log::Builder::new()
@@ -16,16 +28,21 @@ pub fn run() {
                 .target(tauri_plugin_log::Target::new(
                     tauri_plugin_log::TargetKind::Webview,
                 ))
-                .target(tauri_plugin_log::Target::new(
-                    tauri_plugin_log::TargetKind::Folder {
-                        path: current_dir.join("logs"),
-                        file_name: None,
-                    },
-                ))
+                .target(tauri_plugin_log::Target::new(log_target))
                 .build(),
         )
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            let data_dir = db::data_dir::resolve_data_dir(app.handle())?;
+            log::info!("Data directory: {}", data_dir.display());
+            db::db_init::set_db_dir(data_dir)?;
+            //后台检查并续期kerberos票据
+            spawn_ticket_renewal(app.handle().clone());
+            //后台运行排队的上传下载任务
+            spawn_transfer_queue(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             //获取hdfs配置列表
             get_hdfs_config_list,
@@ -37,8 +54,16 @@ pub fn run() {
             get_hdfs_file_list,
             //删除hdfs配置
             delete_hdfs_config,
+            //已删除的hdfs配置列表
+            get_deleted_hdfs_config_list,
+            //恢复已删除的hdfs配置
+            restore_hdfs_config,
+            //彻底删除hdfs配置
+            purge_hdfs_config,
             //上传文件到hdfs
             upload_hdfs_file,
+            //上传本地目录到hdfs
+            upload_hdfs_folder,
             //删除hdfs文件
             delete_hdfs_files,
             //新建目录
@@ -61,6 +86,8 @@ pub fn run() {
             get_hdfs_file,
             //改名
             rename_hdfs_file,
+            //在hdfs内复制文件或目录
+            copy_hdfs_files,
             //获取hdfs文件acl列表
             get_hdfs_file_acl_list,
             //添加acl
@@ -97,6 +124,50 @@ pub fn run() {
             download_folder,
             //查看avro数据
             get_avro_content,
+            //从core-site.xml/hdfs-site.xml导入hdfs配置
+            import_hdfs_config_from_dir,
+            //导出hdfs配置
+            export_hdfs_config_bundle,
+            //导入hdfs配置
+            import_hdfs_config_bundle,
+            //获取配置加密状态
+            get_secret_store_status,
+            //解锁加密配置
+            unlock_secret_store,
+            //更换主密钥
+            rotate_secret_key,
+            //测试hdfs连接
+            test_hdfs_connection,
+            //断开hdfs连接
+            disconnect_hdfs,
+            //获取kerberos票据状态
+            get_kerberos_ticket_status,
+            //列出kerberos票据
+            get_kerberos_tickets,
+            //解析keytab
+            inspect_keytab,
+            //集群容量、安全模式和namenode HA角色
+            get_cluster_status,
+            //取消上传或下载
+            cancel_transfer,
+            //添加上传下载任务到队列
+            enqueue_transfer_job,
+            //上传下载任务列表
+            list_transfer_jobs,
+            //暂停任务
+            pause_transfer_job,
+            //继续任务
+            resume_transfer_job,
+            //取消任务
+            cancel_transfer_job,
+            //重试任务
+            retry_transfer_job,
+            //删除任务
+            remove_transfer_job,
+            //获取同时运行的任务数
+            get_transfer_queue_concurrency,
+            //设置同时运行的任务数
+            set_transfer_queue_concurrency,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
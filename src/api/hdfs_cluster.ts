@@ -0,0 +1,33 @@
+import { invoke } from "@tauri-apps/api/core";
+//单个namenode的状态
+export interface NamenodeStatus {
+  name: string; //HA配置中的namenode名称,非HA时为空
+  rpc_address: string;
+  http_address: string;
+  state: string; //active/standby/observer,无法获取时为空
+  error: string;
+}
+//集群容量和块统计
+export interface ClusterFsStats {
+  capacity: number;
+  used: number;
+  remaining: number;
+  under_replicated_blocks: number;
+  corrupt_blocks: number;
+  missing_blocks: number;
+  live_datanodes: number;
+  dead_datanodes: number;
+}
+//集群状态
+export interface ClusterStatus {
+  fs_stats: ClusterFsStats | null; //没有可访问的active namenode时为空
+  safemode: Boolean;
+  safemode_message: string;
+  namenodes: Array<NamenodeStatus>;
+  auth_failed: Boolean; //没有active namenode且web接口拒绝了认证
+}
+//获取集群容量、安全模式和namenode HA角色
+export const getClusterStatus = async (id: number) => {
+  const result: ClusterStatus = await invoke("get_cluster_status", { id: id });
+  return result;
+};
@@ -6,6 +6,7 @@ export interface HdfsConfig {
   hdfs_config?: string; //json其他配置
   hdfs_url?: string;
   del_flag?: number; //0正常 1删除
+  proxy_user?: string; //代理用户(doAs),为空时不代理
 }
 //获取HDFS连接配置列表
 export const getHdfsConfigList = async () => {
@@ -13,9 +14,17 @@ export const getHdfsConfigList = async () => {
   return result;
 };
 
-//保证HDFS连接配置
+//HDFS连接配置校验结果,key为配置项(hdfs_url、name、hdfs_config或json中的key)
+export interface HdfsConfigValidation {
+  errors: Record<string, Array<string>>;
+  warnings: Record<string, Array<string>>;
+}
+//保证HDFS连接配置,有错误时不保存
 export const saveHdfsConfig = async (hdfs_config: HdfsConfig) => {
-  await invoke("save_hdfs_config", { hdfsConfig: hdfs_config });
+  const result: HdfsConfigValidation = await invoke("save_hdfs_config", {
+    hdfsConfig: hdfs_config,
+  });
+  return result;
 };
 
 //获取HDFS连接配置列表
@@ -27,9 +36,96 @@ export const getHdfsConfig = async (id: number) => {
 export const deleteHdfsConfig = async (id: number) => {
   await invoke("delete_hdfs_config", { id: id });
 };
+//获取已删除的HDFS连接配置列表
+export const getDeletedHdfsConfigList = async () => {
+  const result: Array<HdfsConfig> = await invoke("get_deleted_hdfs_config_list", {});
+  return result;
+};
+//恢复已删除的HDFS连接配置
+export const restoreHdfsConfig = async (id: number) => {
+  await invoke("restore_hdfs_config", { id: id });
+};
+//彻底删除已删除的HDFS连接配置,不传id时清空全部
+export const purgeHdfsConfig = async (id?: number) => {
+  const result: number = await invoke("purge_hdfs_config", { id: id });
+  return result;
+};
+
 
+//初始化连接,没有keytab时需要输入kerberos密码,save_password为true时加密保存密码
+export const initConnection = async (id: number, password?: string, save_password?: Boolean) => {
+  await invoke("init_connection", { id: id, password: password, savePassword: save_password });
+};
+//没有keytab也没有保存密码时init_connection返回的错误前缀
+export const KERBEROS_PASSWORD_REQUIRED = "Kerberos password required";
 
-//初始化连接
-export const initConnection = async (id: number) => {
-  await invoke("init_connection", { id: id });
+//从core-site.xml/hdfs-site.xml导入的配置
+export interface HdfsConfigImport {
+  hdfs_config: HdfsConfig;
+  files: Array<string>;
+  ignored_keys: Array<string>;
+  unresolved_vars: Array<string>;
+}
+//从hadoop配置目录导入HDFS连接配置
+export const importHdfsConfigFromDir = async (config_dir: string, name?: string) => {
+  const result: HdfsConfigImport = await invoke("import_hdfs_config_from_dir", {
+    configDir: config_dir,
+    name: name,
+  });
+  return result;
+};
+
+//导入结果
+export interface HdfsConfigBundleImportItem {
+  name: string;
+  action: string;
+  saved_name: string;
+  removed_keys: Array<string>;
+  replaced_hdfs_url: string | null; //合并时被替换的原hdfs_url
+}
+//导出HDFS连接配置,文件扩展名为.toml时导出toml,include_secrets为false时敏感值导出为掩码
+export const exportHdfsConfigBundle = async (
+  ids: Array<number>,
+  file_path: string,
+  include_secrets: Boolean = false
+) => {
+  const result: number = await invoke("export_hdfs_config_bundle", {
+    ids: ids,
+    filePath: file_path,
+    includeSecrets: include_secrets,
+  });
+  return result;
+};
+//导入HDFS连接配置,conflict_policy: merge/rename/skip
+export const importHdfsConfigBundle = async (
+  file_path: string,
+  conflict_policy: string,
+  exclude_local_paths: Boolean
+) => {
+  const result: Array<HdfsConfigBundleImportItem> = await invoke("import_hdfs_config_bundle", {
+    filePath: file_path,
+    conflictPolicy: conflict_policy,
+    excludeLocalPaths: exclude_local_paths,
+  });
+  return result;
+};
+
+//配置加密状态
+export interface SecretStoreStatus {
+  source: string; //file 密钥文件 passphrase 口令
+  unlocked: Boolean;
+  key_file: string;
+}
+//获取配置加密状态
+export const getSecretStoreStatus = async () => {
+  const result: SecretStoreStatus = await invoke("get_secret_store_status", {});
+  return result;
+};
+//输入主口令解锁
+export const unlockSecretStore = async (passphrase: string) => {
+  await invoke("unlock_secret_store", { passphrase: passphrase });
+};
+//更换主密钥,不传口令时生成新的密钥文件
+export const rotateSecretKey = async (passphrase?: string) => {
+  await invoke("rotate_secret_key", { passphrase: passphrase });
 };
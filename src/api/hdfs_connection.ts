@@ -0,0 +1,28 @@
+import { invoke } from "@tauri-apps/api/core";
+
+//连接测试单步结果
+export interface ConnectionTestStep {
+  name: string;
+  success: Boolean;
+  skipped: Boolean;
+  message: string;
+  elapsed_ms: number;
+}
+//连接测试结果
+export interface ConnectionTestResult {
+  success: Boolean;
+  steps: Array<ConnectionTestStep>;
+  active_namenode?: string;
+  //rpc实际使用的用户,配置了代理用户时为代理用户
+  user?: string;
+  rpc_latency_ms?: number;
+}
+//测试HDFS连接
+export const testHdfsConnection = async (id: number) => {
+  const result: ConnectionTestResult = await invoke("test_hdfs_connection", { id: id });
+  return result;
+};
+//断开HDFS连接,下次访问时重新建立
+export const disconnectHdfs = async (id: number) => {
+  await invoke("disconnect_hdfs", { id: id });
+};
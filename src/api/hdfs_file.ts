@@ -50,29 +50,94 @@ export const getHdfsFile = async (id: number, file_path: string) => {
   return result;
 };
 
-//上传文件
+//目标文件已存在时的处理方式
+export type ConflictPolicy =
+  | "fail"
+  | "overwrite"
+  | "skip"
+  | "rename"
+  | "skip_if_same";
+//写入选项,permission和umask为八进制数值,只能设置其中一个
+export interface UploadOptions {
+  conflict: ConflictPolicy;
+  replication: number | null;
+  block_size: number | null;
+  permission: number | null;
+  umask: number | null;
+}
+//上传文件,进度通过transfer_id对应的事件返回,结果为实际写入的路径,跳过时为空
 export const uploadHdfsFile = async (
   id: number,
   parent_path: string,
-  local_file_path: string
+  local_file_path: string,
+  transfer_id: string,
+  upload_options: UploadOptions | null = null
 ) => {
-  const result: Boolean = await invoke("upload_hdfs_file", {
+  const result: string | null = await invoke("upload_hdfs_file", {
     id: id,
     parentPath: parent_path,
     localFilePath: local_file_path,
+    transferId: transfer_id,
+    uploadOptions: upload_options,
   });
   return result;
 };
-//写入文本到文件
+//上传目录的选项,include/exclude为glob,不含/时匹配文件名,否则匹配相对路径
+export interface FolderUploadOptions {
+  include: Array<string>;
+  exclude: Array<string>;
+  skip_hidden: Boolean;
+  concurrency: number | null;
+  //不再上传的相对路径,传输队列续传时使用
+  skip_paths?: Array<string>;
+}
+//单个文件的上传结果
+export interface FileUploadResult {
+  local_path: string;
+  target_path: string;
+  length: number;
+  skipped: Boolean;
+  error: string | null;
+}
+//目录上传结果
+export interface FolderUploadReport {
+  uploaded: number;
+  failed: number;
+  skipped: number;
+  bytes: number;
+  files: Array<FileUploadResult>;
+}
+//上传本地目录,进度通过transfer_id对应的事件返回
+export const uploadHdfsFolder = async (
+  id: number,
+  parent_path: string,
+  local_folder_path: string,
+  transfer_id: string,
+  options: FolderUploadOptions,
+  upload_options: UploadOptions | null = null
+) => {
+  const result: FolderUploadReport = await invoke("upload_hdfs_folder", {
+    id: id,
+    parentPath: parent_path,
+    localFolderPath: local_folder_path,
+    transferId: transfer_id,
+    options: options,
+    uploadOptions: upload_options,
+  });
+  return result;
+};
+//写入文本到文件,默认覆盖,结果为实际写入的路径,跳过时为空
 export const writeTextToHdfsFile = async (
   id: number,
   file_path: string,
-  content: string
+  content: string,
+  upload_options: UploadOptions | null = null
 ) => {
-  const result: Boolean = await invoke("write_text_hdfs_file", {
+  const result: string | null = await invoke("write_text_hdfs_file", {
     id: id,
     filePath: file_path,
     content: content,
+    uploadOptions: upload_options,
   });
   return result;
 };
@@ -159,6 +224,52 @@ export const renameHdfsFile = async (
   return result;
 };
 
+//hdfs内复制的选项,preserve_*为保留源文件的属性
+export interface CopyOptions {
+  conflict?: ConflictPolicy;
+  preserve_permission?: Boolean;
+  preserve_owner?: Boolean;
+  preserve_times?: Boolean;
+  preserve_acls?: Boolean;
+  concurrency?: number | null;
+}
+//单个文件的复制结果
+export interface FileCopyResult {
+  source_path: string;
+  target_path: string;
+  length: number;
+  skipped: Boolean;
+  error: string | null;
+  //已复制,但保留属性失败
+  warning: string | null;
+}
+//复制结果,warnings为保留属性失败的文件和目录数
+export interface CopyReport {
+  copied: number;
+  failed: number;
+  skipped: number;
+  warnings: number;
+  bytes: number;
+  files: Array<FileCopyResult>;
+}
+//复制文件或目录到target_parent_path下,进度通过transfer_id对应的事件返回
+export const copyHdfsFiles = async (
+  id: number,
+  source_paths: Array<string>,
+  target_parent_path: string,
+  transfer_id: string,
+  options: CopyOptions | null = null
+) => {
+  const result: CopyReport = await invoke("copy_hdfs_files", {
+    id: id,
+    sourcePaths: source_paths,
+    targetParentPath: target_parent_path,
+    transferId: transfer_id,
+    options: options,
+  });
+  return result;
+};
+
 //查看文件预览内容
 export const get_file_preview_content = async (
   id: number,
@@ -182,30 +293,58 @@ export const get_file_content = async (id: number, file_path: string) => {
   return result;
 };
 
-//下载文件
+//下载选项
+export interface DownloadOptions {
+  concurrency: number | null;
+}
+//单个文件的下载结果
+export interface FileDownloadResult {
+  source_path: string;
+  local_path: string;
+  length: number;
+  resumed_from: number;
+  skipped: Boolean;
+  error: string | null;
+}
+//目录下载结果
+export interface FolderDownloadReport {
+  downloaded: number;
+  failed: number;
+  skipped: number;
+  bytes: number;
+  files: Array<FileDownloadResult>;
+}
+
+//下载文件,进度通过transfer_id对应的事件返回
 export const download_file = async (
   id: number,
   source_file_path: string,
-  target_file_parent_path: string
+  target_file_parent_path: string,
+  transfer_id: string
 ) => {
-  const result: string = await invoke("download_file", {
+  const result: FileDownloadResult = await invoke("download_file", {
     id: id,
     sourceFilePath: source_file_path,
     targetFileParentPath: target_file_parent_path,
+    transferId: transfer_id,
   });
   return result;
 };
 
-//下载目录
+//下载目录,进度通过transfer_id对应的事件返回
 export const download_folder = async (
   id: number,
   source_file_path: string,
-  target_file_parent_path: string
+  target_file_parent_path: string,
+  transfer_id: string,
+  options: DownloadOptions | null = null
 ) => {
-  const result: string = await invoke("download_folder", {
+  const result: FolderDownloadReport = await invoke("download_folder", {
     id: id,
     sourceFilePath: source_file_path,
     targetFileParentPath: target_file_parent_path,
+    transferId: transfer_id,
+    options: options,
   });
   return result;
 };
@@ -0,0 +1,87 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+//票据状态变化事件
+export const KERBEROS_TICKET_EVENT = "kerberos-ticket-status";
+//kerberos票据状态,时间为unix秒
+export interface KerberosTicketStatus {
+  id: number;
+  principal: string;
+  end_time: number;
+  renew_till: number;
+  expires_in_secs: number;
+  state:
+    | "valid"
+    | "renewed"
+    | "expiring"
+    | "expired"
+    | "login_required"
+    | "error";
+  message: string;
+}
+//获取连接当前的票据状态,没有kerberos登录时为空
+export const getKerberosTicketStatus = async (id: number) => {
+  const result: KerberosTicketStatus | null = await invoke(
+    "get_kerberos_ticket_status",
+    { id: id }
+  );
+  return result;
+};
+//监听后台票据检查结果
+export const onKerberosTicketStatus = async (
+  callback: (status: KerberosTicketStatus) => void
+): Promise<UnlistenFn> => {
+  return await listen<KerberosTicketStatus>(KERBEROS_TICKET_EVENT, (event) =>
+    callback(event.payload)
+  );
+};
+//票据缓存中的一张票据,时间为unix秒
+export interface KerberosTicket {
+  client: string;
+  server: string;
+  enctype: number;
+  enctype_name: string;
+  flags: string;
+  flag_names: Array<string>;
+  auth_time: number;
+  start_time: number;
+  end_time: number;
+  renew_till: number;
+  expired: Boolean;
+}
+//klist风格的票据列表
+export interface KerberosTicketList {
+  ccache: string;
+  connection_ccache: Boolean;
+  version: number;
+  default_principal: string;
+  tickets: Array<KerberosTicket>;
+}
+//列出连接正在使用的票据缓存中的票据
+export const getKerberosTickets = async (id: number) => {
+  const result: KerberosTicketList = await invoke("get_kerberos_tickets", {
+    id: id,
+  });
+  return result;
+};
+//keytab条目
+export interface KeytabEntry {
+  principal: string;
+  kvno: number;
+  enctype: number;
+  enctype_name: string;
+  timestamp: number;
+  supported: Boolean;
+}
+//keytab内容,principals为每个principal登录时使用的条目
+export interface KeytabInfo {
+  entries: Array<KeytabEntry>;
+  principals: Array<KeytabEntry>;
+}
+//解析keytab
+export const inspectKeytab = async (keytab_path: string) => {
+  const result: KeytabInfo = await invoke("inspect_keytab", {
+    keytabPath: keytab_path,
+  });
+  return result;
+};
@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+//传输进度事件
+export const TRANSFER_PROGRESS_EVENT = "hdfs-transfer-progress";
+//取消的传输返回的错误前缀
+export const TRANSFER_CANCELLED = "Transfer cancelled";
+//传输进度,rate为字节/秒
+export interface TransferProgress {
+  transfer_id: string;
+  id: number;
+  source: string;
+  target: string;
+  bytes: number;
+  total: number;
+  files_done: number;
+  files_total: number;
+  rate: number;
+  eta_secs: number | null;
+  state: "running" | "done" | "cancelled" | "failed";
+  message: string;
+}
+//生成传输id
+export const newTransferId = () => {
+  return crypto.randomUUID();
+};
+//监听传输进度
+export const onTransferProgress = async (
+  callback: (progress: TransferProgress) => void
+): Promise<UnlistenFn> => {
+  return await listen<TransferProgress>(TRANSFER_PROGRESS_EVENT, (event) =>
+    callback(event.payload)
+  );
+};
+//取消传输
+export const cancelTransfer = async (transfer_id: string) => {
+  const result: Boolean = await invoke("cancel_transfer", {
+    transferId: transfer_id,
+  });
+  return result;
+};
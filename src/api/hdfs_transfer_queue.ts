@@ -0,0 +1,110 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import {
+  DownloadOptions,
+  FolderUploadOptions,
+  UploadOptions,
+} from "./hdfs_file.ts";
+
+//任务变化事件
+export const TRANSFER_JOB_EVENT = "hdfs-transfer-job";
+//任务类型,上传时source为本地路径、target为hdfs目录,下载时相反
+export type TransferKind = "upload_file" | "upload_folder" | "download";
+//任务状态
+export type JobState =
+  | "queued"
+  | "running"
+  | "paused"
+  | "done"
+  | "failed"
+  | "cancelled";
+//任务选项,按任务类型使用其中一部分
+export interface TransferJobOptions {
+  upload?: UploadOptions | null;
+  folder?: FolderUploadOptions | null;
+  download?: DownloadOptions | null;
+}
+//传输队列中的任务,时间为unix秒,运行中的任务带实时进度
+export interface TransferJob {
+  id: number;
+  hdfs_config_id: number;
+  kind: TransferKind;
+  source: string;
+  target: string;
+  options: TransferJobOptions;
+  state: JobState;
+  bytes: number;
+  total: number;
+  files_done: number;
+  files_total: number;
+  attempts: number;
+  next_attempt_at: number;
+  done_files: Array<string>;
+  message: string;
+  created_at: string;
+  updated_at: string;
+  transfer_id: string;
+  rate: number;
+  eta_secs: number | null;
+}
+//添加任务到队列
+export const enqueueTransferJob = async (
+  id: number,
+  kind: TransferKind,
+  source: string,
+  target: string,
+  options: TransferJobOptions | null = null
+) => {
+  const result: TransferJob = await invoke("enqueue_transfer_job", {
+    id: id,
+    kind: kind,
+    source: source,
+    target: target,
+    options: options,
+  });
+  return result;
+};
+//任务列表
+export const listTransferJobs = async () => {
+  const result: Array<TransferJob> = await invoke("list_transfer_jobs");
+  return result;
+};
+//监听任务变化
+export const onTransferJob = async (
+  callback: (job: TransferJob) => void
+): Promise<UnlistenFn> => {
+  return await listen<TransferJob>(TRANSFER_JOB_EVENT, (event) =>
+    callback(event.payload)
+  );
+};
+//暂停任务
+export const pauseTransferJob = async (job_id: number) => {
+  await invoke("pause_transfer_job", { jobId: job_id });
+};
+//继续任务
+export const resumeTransferJob = async (job_id: number) => {
+  await invoke("resume_transfer_job", { jobId: job_id });
+};
+//取消任务
+export const cancelTransferJob = async (job_id: number) => {
+  await invoke("cancel_transfer_job", { jobId: job_id });
+};
+//重试任务
+export const retryTransferJob = async (job_id: number) => {
+  await invoke("retry_transfer_job", { jobId: job_id });
+};
+//删除任务
+export const removeTransferJob = async (job_id: number) => {
+  await invoke("remove_transfer_job", { jobId: job_id });
+};
+//同时运行的任务数
+export const getTransferQueueConcurrency = async () => {
+  const result: number = await invoke("get_transfer_queue_concurrency");
+  return result;
+};
+export const setTransferQueueConcurrency = async (concurrency: number) => {
+  const result: number = await invoke("set_transfer_queue_concurrency", {
+    concurrency: concurrency,
+  });
+  return result;
+};